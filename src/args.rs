@@ -1,45 +1,252 @@
+use std::io::IsTerminal;
+
 use chrono::{DateTime, Utc};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use encoding_rs::{Encoding, UTF_8};
+use regex::Regex;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[clap(author, version, about, long_about = None)]
 pub struct Args {
-    /// Location where the data is located
-    pub path: String,
+    /// Location(s) where the data is located (- for stdin), optionally named as name=location.
+    /// Unnamed locations are registered as tbl, tbl2, tbl3, ...
+    #[clap(required_unless_present = "glue_catalog")]
+    pub path: Vec<String>,
 
-    /// Query to execute
-    #[clap(short, long, default_value_t = String::from("select * from tbl"), group = "sql")]
-    pub query: String,
+    /// When provided the glue catalog is registered, so that tables can be queried as glue.<database>.<table>
+    #[clap(long)]
+    pub glue_catalog: bool,
 
-    /// When provided the schema is shown
+    /// Query to execute, defaults to select * from tbl
     #[clap(short, long, group = "sql")]
+    pub query: Option<String>,
+
+    /// When provided the schema is shown
+    #[clap(short, long, group = "sql", requires = "path")]
     pub schema: bool,
 
+    /// Start an interactive session, the default when no query is provided in a terminal
+    #[clap(short, long, conflicts_with_all = ["query", "schema", "output"])]
+    pub interactive: bool,
+
+    /// Optional start (version or timestamp) of the change data feed of a delta table to register instead of the table
+    #[clap(long, value_parser = parse_cdf_bound, conflicts_with_all = ["at", "table_version"])]
+    pub cdf_from: Option<CdfBound>,
+
+    /// Optional end (version or timestamp) of the change data feed of a delta table
+    #[clap(long, value_parser = parse_cdf_bound, requires = "cdf_from")]
+    pub cdf_to: Option<CdfBound>,
+
+    /// When provided the parquet metadata (a row per column chunk per row group) is registered as tbl instead of the data
+    #[clap(long, conflicts_with_all = ["cdf_from", "history", "details"])]
+    pub parquet_meta: bool,
+
+    /// When provided count, null count, distinct count, min, max, mean, stddev and top values are shown per column
+    #[clap(long, requires = "path", conflicts_with_all = ["query", "schema", "interactive", "history", "details"])]
+    pub describe: bool,
+
+    /// When provided the commit log of the delta table is shown
+    #[clap(long, requires = "path", conflicts_with_all = ["query", "schema", "interactive", "details"])]
+    pub history: bool,
+
+    /// When provided the metadata of the delta table is shown
+    #[clap(long, requires = "path", conflicts_with_all = ["query", "schema", "interactive"])]
+    pub details: bool,
+
     /// Rows to return
     #[clap(short, long, default_value_t = 10)]
     pub limit: usize,
 
+    /// Format used to show the result
+    #[clap(short, long, value_enum, default_value_t = PrintFormat::Table)]
+    pub format: PrintFormat,
+
+    /// When provided the header is omitted from csv, tsv and markdown output
+    #[clap(long)]
+    pub no_header: bool,
+
+    /// Optional header (name: value) to send with http(s) requests, can be provided multiple times
+    #[clap(long)]
+    pub http_header: Vec<String>,
+
+    /// Optional bearer token to send with http(s) requests
+    #[clap(long)]
+    pub http_bearer_token: Option<String>,
+
+    /// Format of the data, detected from the data (stdin) or file extension when not provided
+    #[clap(long, value_enum)]
+    pub input_format: Option<InputFormat>,
+
+    /// Delimiter of csv data, eg: ';' or '\t' (implies --input-format csv)
+    #[clap(long, value_parser = parse_byte)]
+    pub csv_delimiter: Option<u8>,
+
+    /// Quote character of csv data (implies --input-format csv)
+    #[clap(long, value_parser = parse_byte)]
+    pub csv_quote: Option<u8>,
+
+    /// Escape character of csv data (implies --input-format csv)
+    #[clap(long, value_parser = parse_byte)]
+    pub csv_escape: Option<u8>,
+
+    /// Lines starting with this character are skipped in csv data (implies --input-format csv)
+    #[clap(long, value_parser = parse_byte)]
+    pub csv_comment: Option<u8>,
+
+    /// Values which are read as null in csv data, eg: NA (implies --input-format csv)
+    #[clap(long)]
+    pub csv_null_value: Option<String>,
+
+    /// When provided the first line of csv data is read as data instead of header (implies --input-format csv)
+    #[clap(long)]
+    pub csv_no_header: bool,
+
+    /// Encoding of csv data, eg: windows-1252, iso-8859-15 or utf-16le (implies --input-format csv).
+    /// Data in another encoding than UTF-8 is converted to UTF-8 in memory before it is read
+    #[clap(long, value_parser = parse_encoding)]
+    pub csv_encoding: Option<&'static Encoding>,
+
+    /// When provided json data is read as a json array of objects instead of newline delimited json
+    /// (implies --input-format json). The array is converted to newline delimited json in memory
+    #[clap(long)]
+    pub json_array: bool,
+
+    /// Number of records which are read to infer the schema of csv and json data
+    #[clap(long)]
+    pub schema_infer_max_records: Option<usize>,
+
+    /// Hive style partition columns with their type, eg: dt:date,region:string.
+    /// Inferred from the directory layout (eg: dt=2024-01-01/region=eu/) when not provided
+    #[clap(long, value_delimiter = ',', value_parser = parse_partition_col)]
+    pub partition_cols: Vec<(String, DataType)>,
+
+    /// Sheet of a spreadsheet (xlsx, xlsm, xlsb, xls or ods) to read, by name or index (starting at 1).
+    /// Defaults to the first sheet (implies --input-format spreadsheet)
+    #[clap(long)]
+    pub sheet: Option<String>,
+
+    /// Cells of the sheet to read, eg: B3:F100. Defaults to the used cells of the sheet (implies --input-format spreadsheet)
+    #[clap(long, value_parser = parse_cell_range)]
+    pub sheet_range: Option<CellRange>,
+
+    /// Row (starting at 1 in the sheet range) with the column names, rows above it are skipped.
+    /// Use 0 for sheets without header
+    #[clap(long, default_value_t = 1)]
+    pub sheet_header_row: usize,
+
+    /// When provided the schema of glue tables is inferred from the data files instead of taken from the catalog
+    #[clap(long)]
+    pub infer_schema: bool,
+
     /// Optional AWS Profile to use
     #[clap(short, long)]
     pub profile: Option<String>,
 
-    /// Optional timestamp for delta table
+    /// Optional timestamp for delta or iceberg table
     #[clap(
         short,
         long,
-        help = "Timestamp to load deltatable in RFC format, eg: 2022-01-13T16:39:00+01:00"
+        help = "Timestamp to load deltatable, iceberg or hudi table in RFC format, eg: 2022-01-13T16:39:00+01:00"
     )]
     pub at: Option<DateTime<Utc>>,
+
+    /// Optional snapshot id for iceberg table
+    #[clap(long, conflicts_with = "at")]
+    pub snapshot_id: Option<i64>,
+
+    /// Optional version for delta table (--version is taken by the qv version)
+    #[clap(long, conflicts_with_all = ["at", "snapshot_id"])]
+    pub table_version: Option<i64>,
+
+    /// Optional location to write the result to instead of showing it, eg: s3://bucket/result.parquet
+    #[clap(short, long)]
+    pub output: Option<String>,
+
+    /// Format of the output, derived from the extension of the output location when not provided
+    #[clap(long, value_enum, requires = "output")]
+    pub output_format: Option<OutputFormat>,
+
+    /// Compression codec for the output, eg: snappy, zstd(3) for parquet or gzip, zstd for csv
+    #[clap(long, requires = "output")]
+    pub compression: Option<String>,
 }
 
 impl Args {
-    pub fn get_query(&self) -> &str {
-        let query = if self.schema {
-            "SELECT column_name, data_type, is_nullable FROM information_schema.columns WHERE table_name = 'tbl'"
+    pub fn get_query(&self) -> String {
+        if self.schema {
+            let table_names = self
+                .get_table_paths()
+                .into_iter()
+                .map(|(table_name, _)| table_name)
+                .collect::<Vec<_>>();
+            schema_query(&table_names)
         } else {
-            self.query.as_str()
-        };
-        query
+            self.query
+                .clone()
+                .unwrap_or_else(|| String::from("select * from tbl"))
+        }
+    }
+
+    /// Returns the (table name, location) pairs for the provided paths
+    pub fn get_table_paths(&self) -> Vec<(String, String)> {
+        self.path
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                parse_named_path(path).unwrap_or_else(|| {
+                    let table_name = if i == 0 {
+                        String::from("tbl")
+                    } else {
+                        format!("tbl{}", i + 1)
+                    };
+                    (table_name, path.clone())
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the explicitly provided input format, csv when csv options (spreadsheet when sheet options) are provided
+    pub fn get_input_format(&self) -> Option<InputFormat> {
+        let has_csv_options = self.csv_delimiter.is_some()
+            || self.csv_quote.is_some()
+            || self.csv_escape.is_some()
+            || self.csv_comment.is_some()
+            || self.csv_null_value.is_some()
+            || self.csv_no_header
+            || self.csv_encoding.is_some();
+        let has_sheet_options = self.sheet.is_some() || self.sheet_range.is_some();
+        self.input_format
+            .or_else(|| has_csv_options.then_some(InputFormat::Csv))
+            .or_else(|| self.json_array.then_some(InputFormat::Json))
+            .or_else(|| has_sheet_options.then_some(InputFormat::Spreadsheet))
+    }
+
+    /// Returns true when the data has to be converted before it can be read (see --csv-encoding, --csv-null-value and --json-array)
+    pub fn needs_transcoding(&self) -> bool {
+        self.csv_encoding.is_some_and(|encoding| encoding != UTF_8)
+            || self.csv_null_value.is_some()
+            || self.json_array
+    }
+
+    /// Returns the options for the tables of the glue catalog, which are not provided as path
+    pub fn get_catalog_options(&self) -> CatalogOptions {
+        CatalogOptions {
+            profile: self.profile.clone(),
+            infer_schema: self.infer_schema,
+        }
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.interactive
+            || (self.query.is_none()
+                && !self.schema
+                && !self.describe
+                && !self.history
+                && !self.details
+                && self.output.is_none()
+                && std::io::stdin().is_terminal()
+                && std::io::stdout().is_terminal())
     }
 
     /*
@@ -74,3 +281,326 @@ impl Args {
         Ok(globbing_path)
     }*/
 }
+
+/// The options which apply to tables of the glue catalog (eg: glue.db.t), options for the provided
+/// paths (eg: --input-format, --table-version or --parquet-meta) do not apply to them
+#[derive(Debug, Clone, Default)]
+pub struct CatalogOptions {
+    pub profile: Option<String>,
+    pub infer_schema: bool,
+}
+
+impl CatalogOptions {
+    /// Returns args with only these options, to build tables like the provided paths
+    pub fn to_args(&self) -> Args {
+        let mut args = Args::parse_from(["qv", "--glue-catalog"]);
+        args.profile = self.profile.clone();
+        args.infer_schema = self.infer_schema;
+        args
+    }
+}
+
+#[test]
+fn test_catalog_options_to_args() {
+    let args = Args::parse_from([
+        "qv",
+        "data",
+        "--glue-catalog",
+        "--infer-schema",
+        "--profile",
+        "dev",
+        "--input-format",
+        "csv",
+        "--csv-delimiter",
+        ";",
+        "--parquet-meta",
+        "--table-version",
+        "1",
+    ]);
+    let catalog_args = args.get_catalog_options().to_args();
+    assert_eq!(Some("dev".to_string()), catalog_args.profile);
+    assert!(catalog_args.infer_schema);
+    assert!(catalog_args.path.is_empty());
+    assert_eq!(None, catalog_args.get_input_format());
+    assert!(!catalog_args.parquet_meta);
+    assert_eq!(None, catalog_args.table_version);
+}
+
+/// When the provided s looks like name=location (and is not an existing local path) return both parts
+fn parse_named_path(s: &str) -> Option<(String, String)> {
+    let re: Regex = Regex::new(r"^([A-Za-z_]\w*)=(.+)$").unwrap();
+    let captures = re.captures(s)?;
+    if std::path::Path::new(s).exists() {
+        return None;
+    }
+    Some((captures[1].to_string(), captures[2].to_string()))
+}
+
+#[test]
+fn test_parse_named_path() {
+    assert_eq!(
+        None,
+        parse_named_path("./testing/data/csv/aggregate_test_100.csv")
+    );
+    assert_eq!(None, parse_named_path("s3://bucket/events/dt=2024-01-01/"));
+    assert_eq!(
+        None,
+        parse_named_path(
+            "https://s3.console.aws.amazon.com/s3/buckets/b?region=eu-central-1&prefix=x/"
+        )
+    );
+    assert_eq!(
+        Some(("orders".to_string(), "s3://bucket/orders".to_string())),
+        parse_named_path("orders=s3://bucket/orders")
+    );
+    assert_eq!(
+        Some(("lookup".to_string(), "./dim.csv".to_string())),
+        parse_named_path("lookup=./dim.csv")
+    );
+}
+
+pub fn schema_query(table_names: &[String]) -> String {
+    if let [table_name] = table_names {
+        format!("SELECT column_name, data_type, is_nullable FROM information_schema.columns WHERE table_name = '{table_name}'")
+    } else {
+        let table_names = table_names
+            .iter()
+            .map(|table_name| format!("'{table_name}'"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("SELECT table_name, column_name, data_type, is_nullable FROM information_schema.columns WHERE table_name IN ({table_names})")
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputFormat {
+    Parquet,
+    Avro,
+    Csv,
+    Json,
+    Orc,
+    Arrow,
+    /// xlsx, xlsm, xlsb, xls or ods
+    #[value(alias = "excel")]
+    Spreadsheet,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrintFormat {
+    Table,
+    Json,
+    Ndjson,
+    Csv,
+    Tsv,
+    Markdown,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Parquet,
+    Csv,
+    Ndjson,
+    Arrow,
+}
+
+fn parse_byte(s: &str) -> Result<u8, String> {
+    match s {
+        "\\t" | "tab" => Ok(b'\t'),
+        _ if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+        _ => Err(format!("{s} is not a single ascii character")),
+    }
+}
+
+#[test]
+fn test_get_input_format() {
+    let input_format =
+        |args: &[&str]| Args::parse_from([&["qv", "data"][..], args].concat()).get_input_format();
+    assert_eq!(None, input_format(&[]));
+    assert_eq!(
+        Some(InputFormat::Csv),
+        input_format(&["--csv-delimiter", ";"])
+    );
+    assert_eq!(Some(InputFormat::Json), input_format(&["--json-array"]));
+    assert_eq!(
+        Some(InputFormat::Spreadsheet),
+        input_format(&["--sheet", "Budget"])
+    );
+    assert_eq!(
+        Some(InputFormat::Spreadsheet),
+        input_format(&["--input-format", "excel"])
+    );
+    assert_eq!(
+        Some(InputFormat::Orc),
+        input_format(&["--input-format", "orc", "--sheet", "1"])
+    );
+}
+
+#[test]
+fn test_table_flags_require_path() {
+    for flag in ["--schema", "--describe", "--history", "--details"] {
+        assert!(Args::try_parse_from(["qv", "--glue-catalog", flag]).is_err());
+        assert!(Args::try_parse_from(["qv", "--glue-catalog", "glue://db.table", flag]).is_ok());
+    }
+    assert!(Args::try_parse_from(["qv", "--glue-catalog", "-q", "select 1"]).is_ok());
+}
+
+fn parse_encoding(s: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(s.as_bytes()).ok_or_else(|| format!("{s} is not a known encoding"))
+}
+
+#[test]
+fn test_parse_encoding() {
+    assert_eq!(
+        Ok(encoding_rs::WINDOWS_1252),
+        parse_encoding("windows-1252")
+    );
+    assert_eq!(Ok(encoding_rs::WINDOWS_1252), parse_encoding("latin1"));
+    assert_eq!(Ok(encoding_rs::UTF_16LE), parse_encoding("utf-16le"));
+    assert_eq!(Ok(UTF_8), parse_encoding("UTF-8"));
+    assert!(parse_encoding("klingon").is_err());
+}
+
+#[test]
+fn test_parse_byte() {
+    assert_eq!(Ok(b';'), parse_byte(";"));
+    assert_eq!(Ok(b'\t'), parse_byte("\\t"));
+    assert_eq!(Ok(b'\t'), parse_byte("\t"));
+    assert!(parse_byte("ab").is_err());
+    assert!(parse_byte("é").is_err());
+}
+
+fn parse_partition_col(s: &str) -> Result<(String, DataType), String> {
+    let (name, data_type) = s
+        .split_once(':')
+        .ok_or_else(|| format!("{s} is not formatted as name:type"))?;
+    let data_type = match data_type.to_lowercase().as_str() {
+        "string" => DataType::Utf8,
+        "int" => DataType::Int32,
+        "bigint" => DataType::Int64,
+        "double" => DataType::Float64,
+        "boolean" => DataType::Boolean,
+        "date" => DataType::Date32,
+        "timestamp" => DataType::Timestamp(TimeUnit::Microsecond, None),
+        other => {
+            return Err(format!(
+                "No support for partition column type {other} yet, use one of string, int, bigint, double, boolean, date or timestamp"
+            ))
+        }
+    };
+    Ok((name.to_string(), data_type))
+}
+
+#[test]
+fn test_parse_partition_col() {
+    assert_eq!(
+        Ok(("dt".to_string(), DataType::Date32)),
+        parse_partition_col("dt:date")
+    );
+    assert_eq!(
+        Ok(("region".to_string(), DataType::Utf8)),
+        parse_partition_col("region:STRING")
+    );
+    assert!(parse_partition_col("dt").is_err());
+    assert!(parse_partition_col("dt:decimal").is_err());
+}
+
+/// A rectangle of cells in a sheet, as zero based (row, column) positions of the top left and bottom right cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellRange {
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+}
+
+fn parse_cell_range(s: &str) -> Result<CellRange, String> {
+    let (start, end) = s
+        .split_once(':')
+        .ok_or_else(|| format!("{s} is not formatted as a cell range, eg: A1:D100"))?;
+    let start = parse_cell_reference(start)?;
+    let end = parse_cell_reference(end)?;
+    if start.0 > end.0 || start.1 > end.1 {
+        return Err(format!(
+            "{s} does not end below and to the right of its start"
+        ));
+    }
+    Ok(CellRange { start, end })
+}
+
+/// Parses a cell reference (eg: AB12) into its zero based (row, column) position
+fn parse_cell_reference(s: &str) -> Result<(u32, u32), String> {
+    let s = s.trim().replace('$', "").to_uppercase();
+    let digits_at = s
+        .find(|c: char| c.is_ascii_digit())
+        .ok_or_else(|| format!("{s} is not a cell reference, eg: A1"))?;
+    let (letters, digits) = s.split_at(digits_at);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(format!("{s} is not a cell reference, eg: A1"));
+    }
+    let column = letters
+        .bytes()
+        .try_fold(0u32, |column, letter| {
+            column
+                .checked_mul(26)
+                .and_then(|column| column.checked_add(u32::from(letter - b'A' + 1)))
+        })
+        .ok_or_else(|| format!("{s} is not a cell reference, eg: A1"))?;
+    let row = digits
+        .parse::<u32>()
+        .ok()
+        .filter(|row| *row > 0)
+        .ok_or_else(|| format!("{s} is not a cell reference, eg: A1"))?;
+    Ok((row - 1, column - 1))
+}
+
+#[test]
+fn test_parse_cell_range() {
+    assert_eq!(
+        Ok(CellRange {
+            start: (0, 0),
+            end: (99, 3)
+        }),
+        parse_cell_range("A1:D100")
+    );
+    assert_eq!(
+        Ok(CellRange {
+            start: (2, 1),
+            end: (9, 27)
+        }),
+        parse_cell_range("$b$3:AB10")
+    );
+    assert!(parse_cell_range("A1").is_err());
+    assert!(parse_cell_range("A0:B2").is_err());
+    assert!(parse_cell_range("1A:B2").is_err());
+    assert!(parse_cell_range("D4:A1").is_err());
+}
+
+/// Start or end of a delta change data feed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CdfBound {
+    Version(i64),
+    Timestamp(DateTime<Utc>),
+}
+
+fn parse_cdf_bound(s: &str) -> Result<CdfBound, String> {
+    if let Ok(version) = s.parse::<i64>() {
+        return Ok(CdfBound::Version(version));
+    }
+    s.parse::<DateTime<Utc>>()
+        .map(CdfBound::Timestamp)
+        .map_err(|_| {
+            format!(
+                "{s} is not a version or timestamp in RFC format, eg: 2022-01-13T16:39:00+01:00"
+            )
+        })
+}
+
+#[test]
+fn test_parse_cdf_bound() {
+    assert_eq!(Ok(CdfBound::Version(3)), parse_cdf_bound("3"));
+    assert_eq!(
+        Ok(CdfBound::Timestamp(
+            "2022-01-13T15:39:00Z".parse::<DateTime<Utc>>().unwrap()
+        )),
+        parse_cdf_bound("2022-01-13T16:39:00+01:00")
+    );
+    assert!(parse_cdf_bound("yesterday").is_err());
+}
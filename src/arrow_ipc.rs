@@ -0,0 +1,121 @@
+use std::io::Cursor;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::{DataType, SchemaRef};
+use datafusion::arrow::ipc::reader::StreamReader;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::file_format::arrow::ArrowFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+use object_store::ObjectMeta;
+
+use crate::input_format::find_first_data_file;
+
+/// Extensions of arrow ipc files (feather v2 files are arrow ipc files)
+const ARROW_IPC_EXTENSIONS: [&str; 3] = ["arrow", "feather", "ipc"];
+
+/// Arrow ipc files start with the ARROW1 magic
+pub fn is_arrow_file(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"ARROW1")
+}
+
+/// Arrow ipc streams start with the continuation marker of the schema message
+pub fn is_arrow_stream(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0xff, 0xff, 0xff, 0xff])
+}
+
+#[test]
+fn test_is_arrow_file_or_stream() {
+    assert!(is_arrow_file(b"ARROW1\x00\x00\xff\xff\xff\xff"));
+    assert!(!is_arrow_stream(b"ARROW1\x00\x00\xff\xff\xff\xff"));
+    assert!(is_arrow_stream(b"\xff\xff\xff\xff\x78\x00\x00\x00"));
+    assert!(!is_arrow_file(b"PAR1\x15\x04"));
+    assert!(!is_arrow_stream(b"PAR1\x15\x04"));
+}
+
+/// Builds a table when the files at the path are arrow ipc files or streams (.arrow, .feather or .ipc).
+/// Files are read via a listing table (like parquet, with partition columns), streams are loaded in a
+/// memory table. With an explicit input format (force) the files do not need a matching extension.
+/// Returns None when the path does not contain arrow ipc data.
+pub async fn open_arrow_ipc_table(
+    ctx: &SessionContext,
+    data_path: &str,
+    force: bool,
+    partition_cols: &[(String, DataType)],
+) -> Result<Option<Arc<dyn TableProvider>>> {
+    let Some(first_file) = find_first_data_file(ctx, data_path).await? else {
+        return Ok(None);
+    };
+    let extension = match get_arrow_ipc_extension(&first_file) {
+        Some(extension) => format!(".{extension}"),
+        None if force => String::new(),
+        None => return Ok(None),
+    };
+
+    let table_path = ListingTableUrl::parse(data_path)?;
+    let store = ctx.runtime_env().object_store(table_path.object_store())?;
+    // files smaller than the magic can not be arrow ipc data
+    let magic_range = 0..first_file.size.min(6);
+    let magic = store.get_range(&first_file.location, magic_range).await?;
+
+    if is_arrow_file(&magic) {
+        let options = ListingOptions::new(Arc::new(ArrowFormat)).with_file_extension(extension);
+        let table =
+            crate::build_listing_table(data_path, Some(options), None, partition_cols, ctx).await?;
+        return Ok(Some(table));
+    }
+
+    if !is_arrow_stream(&magic) {
+        return Err(DataFusionError::Execution(format!(
+            "{} is not an arrow ipc file or stream",
+            first_file.location
+        )));
+    }
+
+    let state = ctx.state();
+    let objects: Vec<ObjectMeta> = table_path
+        .list_all_files(&state, store.as_ref(), &extension)
+        .await?
+        .try_collect()
+        .await?;
+    let mut schema = None;
+    let mut batches = vec![];
+    for object in objects {
+        let file_name = object.location.filename().unwrap_or_default();
+        if file_name.starts_with(['_', '.']) {
+            continue;
+        }
+        let bytes = store.get(&object.location).await?.bytes().await?;
+        let (stream_schema, stream_batches) = read_arrow_stream(&bytes)?;
+        schema.get_or_insert(stream_schema);
+        batches.extend(stream_batches);
+    }
+    let schema = schema.ok_or_else(|| {
+        DataFusionError::Execution(format!("Could not find arrow ipc streams at {data_path}"))
+    })?;
+    Ok(Some(Arc::new(MemTable::try_new(schema, vec![batches])?)))
+}
+
+/// Builds a memory table with the batches of an arrow ipc stream (eg: from stdin)
+pub fn build_arrow_stream_table(bytes: &[u8]) -> Result<Arc<dyn TableProvider>> {
+    let (schema, batches) = read_arrow_stream(bytes)?;
+    Ok(Arc::new(MemTable::try_new(schema, vec![batches])?))
+}
+
+fn read_arrow_stream(bytes: &[u8]) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let reader = StreamReader::try_new(Cursor::new(bytes), None)?;
+    let schema = reader.schema();
+    let batches = reader.collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok((schema, batches))
+}
+
+fn get_arrow_ipc_extension(object: &ObjectMeta) -> Option<&str> {
+    let (_, extension) = object.location.filename()?.rsplit_once('.')?;
+    ARROW_IPC_EXTENSIONS
+        .iter()
+        .any(|arrow_extension| extension.eq_ignore_ascii_case(arrow_extension))
+        .then_some(extension)
+}
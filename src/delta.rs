@@ -0,0 +1,298 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, Int64Array, StringArray, TimestampMillisecondArray};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::datasource::TableProvider;
+use datafusion::prelude::SessionContext;
+use deltalake::delta_datafusion::DeltaCdfTableProvider;
+use deltalake::{DeltaOps, DeltaTable};
+use futures::{StreamExt, TryStreamExt};
+use object_store::ObjectMeta;
+use serde::Deserialize;
+
+use crate::args::CdfBound;
+
+/// Loads the provided version of the delta table.
+/// When the version can not be loaded, the error lists the versions which are available.
+pub async fn load_delta_version(
+    ctx: &SessionContext,
+    delta_table: &mut DeltaTable,
+    table_path: &str,
+    version: i64,
+) -> Result<()> {
+    if let Err(e) = delta_table.load_version(version).await {
+        let message = match get_version_range(ctx, table_path).await? {
+            Some((first, last)) => format!(
+                "Could not load version {version} of delta table {table_path}, available versions are {first} to {last}: {e}"
+            ),
+            None => format!("Could not load version {version} of delta table {table_path}: {e}"),
+        };
+        return Err(DataFusionError::Execution(message));
+    }
+    Ok(())
+}
+
+/// Builds a table with the change data feed (_change_type, _commit_version and _commit_timestamp
+/// columns added to the data) of the delta table between from and the optional to
+pub fn build_change_data_feed(
+    delta_table: DeltaTable,
+    from: CdfBound,
+    to: Option<CdfBound>,
+) -> Result<Arc<dyn TableProvider>> {
+    let builder = DeltaOps::from(delta_table).load_cdf();
+    let builder = match from {
+        CdfBound::Version(version) => builder.with_starting_version(version),
+        CdfBound::Timestamp(timestamp) => builder.with_starting_timestamp(timestamp),
+    };
+    let builder = match to {
+        Some(CdfBound::Version(version)) => builder.with_ending_version(version),
+        Some(CdfBound::Timestamp(timestamp)) => builder.with_ending_timestamp(timestamp),
+        None => builder,
+    };
+    Ok(Arc::new(DeltaCdfTableProvider::try_new(builder)?))
+}
+
+/// The commitInfo action of a commit, written by most (but not all) writers
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct CommitInfo {
+    timestamp: Option<i64>,
+    in_commit_timestamp: Option<i64>,
+    operation: Option<String>,
+    operation_parameters: Option<serde_json::Value>,
+    operation_metrics: Option<serde_json::Value>,
+    engine_info: Option<String>,
+    client_version: Option<String>,
+    user_name: Option<String>,
+}
+
+/// Returns the commit log of the delta table (up to the loaded version), most recent commit first.
+/// The version of every entry is taken from the name of its commit file, commits which were
+/// removed by log retention are not listed.
+pub async fn get_history(ctx: &SessionContext, delta_table: &DeltaTable) -> Result<RecordBatch> {
+    let current_version = delta_table.version().unwrap_or_default();
+    let log_url = ListingTableUrl::parse(format!(
+        "{}/_delta_log/",
+        delta_table.table_uri().trim_end_matches('/')
+    ))?;
+    let store = ctx.runtime_env().object_store(log_url.object_store())?;
+    let objects: Vec<ObjectMeta> = store.list(Some(log_url.prefix())).try_collect().await?;
+
+    let mut commits = objects
+        .into_iter()
+        .filter_map(|object| {
+            let version = parse_commit_version(object.location.filename()?)?;
+            (version <= current_version).then_some((version, object))
+        })
+        .collect::<Vec<_>>();
+    commits.sort_by_key(|(version, _)| std::cmp::Reverse(*version));
+
+    let commits = futures::stream::iter(commits)
+        .map(|(version, object)| {
+            let store = store.clone();
+            async move {
+                let bytes = store.get(&object.location).await?.bytes().await?;
+                let commit_info = parse_commit_info(&bytes).map_err(|e| {
+                    DataFusionError::Execution(format!("Failed to parse {}: {e}", object.location))
+                })?;
+                // without commitInfo the modification time of the commit file is the commit timestamp
+                let timestamp = commit_info
+                    .as_ref()
+                    .and_then(|commit_info| {
+                        commit_info.in_commit_timestamp.or(commit_info.timestamp)
+                    })
+                    .unwrap_or_else(|| object.last_modified.timestamp_millis());
+                Ok::<_, DataFusionError>((version, timestamp, commit_info.unwrap_or_default()))
+            }
+        })
+        .buffered(10)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let to_json = |value: &Option<serde_json::Value>| value.as_ref().map(|value| value.to_string());
+
+    let versions = commits
+        .iter()
+        .map(|(version, _, _)| *version)
+        .collect::<Vec<_>>();
+    let timestamps = commits
+        .iter()
+        .map(|(_, timestamp, _)| *timestamp)
+        .collect::<Vec<_>>();
+    let operations = commits
+        .iter()
+        .map(|(_, _, commit_info)| commit_info.operation.clone())
+        .collect::<Vec<_>>();
+    let operation_parameters = commits
+        .iter()
+        .map(|(_, _, commit_info)| to_json(&commit_info.operation_parameters))
+        .collect::<Vec<_>>();
+    let operation_metrics = commits
+        .iter()
+        .map(|(_, _, commit_info)| to_json(&commit_info.operation_metrics))
+        .collect::<Vec<_>>();
+    let engine_infos = commits
+        .iter()
+        .map(|(_, _, commit_info)| {
+            commit_info
+                .engine_info
+                .clone()
+                .or_else(|| commit_info.client_version.clone())
+        })
+        .collect::<Vec<_>>();
+    let user_names = commits
+        .iter()
+        .map(|(_, _, commit_info)| commit_info.user_name.clone())
+        .collect::<Vec<_>>();
+
+    let batch = RecordBatch::try_from_iter(vec![
+        ("version", Arc::new(Int64Array::from(versions)) as ArrayRef),
+        (
+            "timestamp",
+            Arc::new(TimestampMillisecondArray::from(timestamps).with_timezone("UTC")),
+        ),
+        ("operation", Arc::new(StringArray::from(operations))),
+        (
+            "operation_parameters",
+            Arc::new(StringArray::from(operation_parameters)),
+        ),
+        (
+            "operation_metrics",
+            Arc::new(StringArray::from(operation_metrics)),
+        ),
+        ("engine_info", Arc::new(StringArray::from(engine_infos))),
+        ("user_name", Arc::new(StringArray::from(user_names))),
+    ])?;
+    Ok(batch)
+}
+
+/// Returns the commitInfo action of a commit file (newline delimited json actions)
+fn parse_commit_info(bytes: &[u8]) -> serde_json::Result<Option<CommitInfo>> {
+    for line in bytes.split(|byte| *byte == b'\n') {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let mut action: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(line)?;
+        if let Some(commit_info) = action.remove("commitInfo") {
+            return serde_json::from_value(commit_info).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+#[test]
+fn test_parse_commit_info() {
+    let commit = br#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}
+{"commitInfo":{"timestamp":1700000000000,"operation":"WRITE","operationParameters":{"mode":"Append"},"engineInfo":"Apache-Spark/3.5.0"}}
+{"add":{"path":"part-00000.parquet","size":1,"modificationTime":0,"dataChange":true}}
+"#;
+    let commit_info = parse_commit_info(commit).unwrap().unwrap();
+    assert_eq!(Some(1700000000000), commit_info.timestamp);
+    assert_eq!(Some("WRITE".to_string()), commit_info.operation);
+    assert_eq!(
+        Some(r#"{"mode":"Append"}"#.to_string()),
+        commit_info
+            .operation_parameters
+            .map(|parameters| parameters.to_string())
+    );
+
+    let commit =
+        br#"{"add":{"path":"part-00000.parquet","size":1,"modificationTime":0,"dataChange":true}}"#;
+    assert!(parse_commit_info(commit).unwrap().is_none());
+}
+
+/// Returns the metadata of the loaded version of the delta table as property/value pairs
+pub fn get_details(delta_table: &DeltaTable) -> Result<RecordBatch> {
+    let snapshot = delta_table.snapshot()?;
+    let metadata = snapshot.metadata();
+    let protocol = snapshot.protocol();
+
+    let mut details: Vec<(String, Option<String>)> = vec![
+        ("location".to_string(), Some(delta_table.table_uri())),
+        (
+            "version".to_string(),
+            delta_table.version().map(|version| version.to_string()),
+        ),
+        ("id".to_string(), Some(metadata.id().to_string())),
+        ("name".to_string(), metadata.name().map(String::from)),
+        (
+            "description".to_string(),
+            metadata.description().map(String::from),
+        ),
+        (
+            "partition_columns".to_string(),
+            Some(metadata.partition_columns().join(", ")),
+        ),
+        (
+            "created_time".to_string(),
+            metadata
+                .created_time()
+                .and_then(chrono::DateTime::from_timestamp_millis)
+                .map(|created_time| created_time.to_rfc3339()),
+        ),
+        (
+            "min_reader_version".to_string(),
+            Some(protocol.min_reader_version().to_string()),
+        ),
+        (
+            "min_writer_version".to_string(),
+            Some(protocol.min_writer_version().to_string()),
+        ),
+        (
+            "num_files".to_string(),
+            Some(snapshot.log_data().num_files().to_string()),
+        ),
+    ];
+
+    let mut configuration = metadata.configuration().iter().collect::<Vec<_>>();
+    configuration.sort();
+    for (key, value) in configuration {
+        details.push((format!("properties.{key}"), Some(value.to_string())));
+    }
+
+    let (properties, values): (Vec<_>, Vec<_>) = details.into_iter().unzip();
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "property",
+            Arc::new(StringArray::from(properties)) as ArrayRef,
+        ),
+        ("value", Arc::new(StringArray::from(values))),
+    ])?;
+    Ok(batch)
+}
+
+/// Returns the first and last version for which a commit is found in the _delta_log of the table
+async fn get_version_range(ctx: &SessionContext, table_path: &str) -> Result<Option<(i64, i64)>> {
+    let log_url =
+        ListingTableUrl::parse(format!("{}/_delta_log/", table_path.trim_end_matches('/')))?;
+    let store = ctx.runtime_env().object_store(log_url.object_store())?;
+    let objects: Vec<ObjectMeta> = store.list(Some(log_url.prefix())).try_collect().await?;
+
+    let versions = objects
+        .iter()
+        .filter_map(|object| parse_commit_version(object.location.filename()?))
+        .collect::<Vec<_>>();
+    Ok(versions
+        .iter()
+        .min()
+        .zip(versions.iter().max())
+        .map(|(first, last)| (*first, *last)))
+}
+
+/// Commits are named <zero padded version>.json
+fn parse_commit_version(file_name: &str) -> Option<i64> {
+    file_name.strip_suffix(".json")?.parse().ok()
+}
+
+#[test]
+fn test_parse_commit_version() {
+    assert_eq!(Some(0), parse_commit_version("00000000000000000000.json"));
+    assert_eq!(Some(12), parse_commit_version("00000000000000000012.json"));
+    assert_eq!(
+        None,
+        parse_commit_version("00000000000000000010.checkpoint.parquet")
+    );
+    assert_eq!(None, parse_commit_version("_last_checkpoint"));
+}
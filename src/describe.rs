@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayRef, Float64Array, Int64Array, StringArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::DataType;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::cast::{as_float64_array, as_int64_array, as_string_array};
+use datafusion::common::{DataFusionError, Result};
+use datafusion::prelude::SessionContext;
+
+use crate::args::schema_query;
+
+/// Number of most frequent values which are shown per column
+const TOP_VALUES: usize = 5;
+
+struct ColumnProfile {
+    column_name: String,
+    data_type: String,
+    count: Option<i64>,
+    null_count: Option<i64>,
+    distinct_count: Option<i64>,
+    min: Option<String>,
+    max: Option<String>,
+    mean: Option<f64>,
+    stddev: Option<f64>,
+    top_values: Option<String>,
+}
+
+/// Computes count, null count, distinct count, min, max, mean, stddev and the most frequent values
+/// for each column of the table. The aggregations depend on the data type of the column as found in
+/// the information_schema.
+pub async fn describe_table(ctx: &SessionContext, table_name: &str) -> Result<RecordBatch> {
+    let columns = get_columns(ctx, table_name).await?;
+    if columns.is_empty() {
+        return to_record_batch(vec![]);
+    }
+
+    let mut aggregations = vec![];
+    for (i, (column_name, data_type)) in columns.iter().enumerate() {
+        aggregations.extend(build_aggregations(i, column_name, data_type));
+    }
+    let query = format!(
+        "SELECT {} FROM {}",
+        aggregations.join(", "),
+        quote_identifier(table_name)
+    );
+    let statistics = ctx.sql(&query).await?.collect().await?;
+    let statistics = statistics.first().ok_or_else(|| {
+        DataFusionError::Execution(format!("Failed to compute statistics for {table_name}"))
+    })?;
+
+    let mut top_values = get_top_values(ctx, table_name, &columns).await?;
+
+    let mut profiles = vec![];
+    for (i, (column_name, data_type)) in columns.into_iter().enumerate() {
+        let top_values =
+            is_groupable(&data_type).then(|| top_values.remove(&i).unwrap_or_default());
+        profiles.push(ColumnProfile {
+            count: get_i64(statistics, &format!("c{i}_count"))?,
+            null_count: get_i64(statistics, &format!("c{i}_null_count"))?,
+            distinct_count: get_i64(statistics, &format!("c{i}_distinct_count"))?,
+            min: get_string(statistics, &format!("c{i}_min"))?,
+            max: get_string(statistics, &format!("c{i}_max"))?,
+            mean: get_f64(statistics, &format!("c{i}_mean"))?,
+            stddev: get_f64(statistics, &format!("c{i}_stddev"))?,
+            top_values,
+            column_name,
+            data_type,
+        });
+    }
+
+    to_record_batch(profiles)
+}
+
+async fn get_columns(ctx: &SessionContext, table_name: &str) -> Result<Vec<(String, String)>> {
+    let batches = ctx
+        .sql(&schema_query(&[table_name.to_string()]))
+        .await?
+        .collect()
+        .await?;
+    let mut columns = vec![];
+    for batch in batches {
+        let column_names = cast(column(&batch, "column_name")?, &DataType::Utf8)?;
+        let data_types = cast(column(&batch, "data_type")?, &DataType::Utf8)?;
+        let column_names = as_string_array(&column_names)?;
+        let data_types = as_string_array(&data_types)?;
+        for row in 0..batch.num_rows() {
+            columns.push((
+                column_names.value(row).to_string(),
+                data_types.value(row).to_string(),
+            ));
+        }
+    }
+    Ok(columns)
+}
+
+/// Builds the aggregations for the column, named c<i>_<statistic>
+fn build_aggregations(i: usize, column_name: &str, data_type: &str) -> Vec<String> {
+    let column = quote_identifier(column_name);
+    let distinct_count = if supports_approx_distinct(data_type) {
+        format!("approx_distinct({column})")
+    } else if is_groupable(data_type) {
+        format!("count(DISTINCT {column})")
+    } else {
+        String::from("CAST(NULL AS BIGINT)")
+    };
+    let (min, max) = if is_groupable(data_type) {
+        (
+            format!("CAST(min({column}) AS VARCHAR)"),
+            format!("CAST(max({column}) AS VARCHAR)"),
+        )
+    } else {
+        (
+            String::from("CAST(NULL AS VARCHAR)"),
+            String::from("CAST(NULL AS VARCHAR)"),
+        )
+    };
+    let (mean, stddev) = if is_numeric(data_type) {
+        (
+            format!("avg(CAST({column} AS DOUBLE))"),
+            format!("stddev(CAST({column} AS DOUBLE))"),
+        )
+    } else {
+        (
+            String::from("CAST(NULL AS DOUBLE)"),
+            String::from("CAST(NULL AS DOUBLE)"),
+        )
+    };
+
+    vec![
+        format!("count({column}) AS c{i}_count"),
+        format!("count(*) - count({column}) AS c{i}_null_count"),
+        format!("{distinct_count} AS c{i}_distinct_count"),
+        format!("{min} AS c{i}_min"),
+        format!("{max} AS c{i}_max"),
+        format!("{mean} AS c{i}_mean"),
+        format!("{stddev} AS c{i}_stddev"),
+    ]
+}
+
+#[test]
+fn test_build_aggregations() {
+    let aggregations = build_aggregations(0, "amount", "Float64");
+    assert_eq!("count(\"amount\") AS c0_count", aggregations[0]);
+    assert_eq!(
+        "count(DISTINCT \"amount\") AS c0_distinct_count",
+        aggregations[2]
+    );
+    assert_eq!(
+        "avg(CAST(\"amount\" AS DOUBLE)) AS c0_mean",
+        aggregations[5]
+    );
+
+    let aggregations = build_aggregations(1, "name", "Utf8");
+    assert_eq!(
+        "approx_distinct(\"name\") AS c1_distinct_count",
+        aggregations[2]
+    );
+    assert_eq!("CAST(NULL AS DOUBLE) AS c1_stddev", aggregations[6]);
+
+    let aggregations = build_aggregations(2, "tags", "List(Field { name: \"item\" })");
+    assert_eq!("CAST(NULL AS VARCHAR) AS c2_min", aggregations[3]);
+}
+
+/// Computes the most frequent values of all groupable columns in a single scan of the table:
+/// every row is unnested into a (column index, value) cell per column, the cells are counted
+/// and ranked per column. Returns the formatted top values by column index.
+async fn get_top_values(
+    ctx: &SessionContext,
+    table_name: &str,
+    columns: &[(String, String)],
+) -> Result<HashMap<usize, String>> {
+    let Some(query) = build_top_values_query(table_name, columns) else {
+        return Ok(HashMap::new());
+    };
+    let batches = ctx.sql(&query).await?.collect().await?;
+    let mut top_values: HashMap<usize, Vec<String>> = HashMap::new();
+    for batch in batches {
+        let column_indexes = cast(column(&batch, "column_index")?, &DataType::Int64)?;
+        let column_indexes = as_int64_array(&column_indexes)?;
+        let values = cast(column(&batch, "value")?, &DataType::Utf8)?;
+        let values = as_string_array(&values)?;
+        let counts = as_int64_array(column(&batch, "value_count")?)?;
+        for row in 0..batch.num_rows() {
+            let value = if values.is_null(row) {
+                "NULL"
+            } else {
+                values.value(row)
+            };
+            top_values
+                .entry(column_indexes.value(row) as usize)
+                .or_default()
+                .push(format!("{value} ({})", counts.value(row)));
+        }
+    }
+    Ok(top_values
+        .into_iter()
+        .map(|(i, values)| (i, values.join(", ")))
+        .collect())
+}
+
+/// Returns None when the table has no groupable columns
+fn build_top_values_query(table_name: &str, columns: &[(String, String)]) -> Option<String> {
+    let cells = columns
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, data_type))| is_groupable(data_type))
+        .map(|(i, (column_name, _))| {
+            format!(
+                "named_struct('column_index', {i}, 'value', CAST({} AS VARCHAR))",
+                quote_identifier(column_name)
+            )
+        })
+        .collect::<Vec<_>>();
+    if cells.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "WITH cells AS (SELECT unnest(make_array({cells})) AS cell FROM {table}), \
+        counts AS (SELECT cell['column_index'] AS column_index, cell['value'] AS value, count(*) AS value_count FROM cells GROUP BY 1, 2) \
+        SELECT column_index, value, value_count FROM (\
+        SELECT column_index, value, value_count, row_number() OVER (PARTITION BY column_index ORDER BY value_count DESC, value) AS value_rank FROM counts\
+        ) WHERE value_rank <= {TOP_VALUES} ORDER BY column_index, value_rank",
+        cells = cells.join(", "),
+        table = quote_identifier(table_name)
+    ))
+}
+
+#[test]
+fn test_build_top_values_query() {
+    let columns = vec![
+        ("id".to_string(), "Int64".to_string()),
+        (
+            "tags".to_string(),
+            "List(Field { name: \"item\" })".to_string(),
+        ),
+        ("name".to_string(), "Utf8".to_string()),
+    ];
+    let query = build_top_values_query("tbl", &columns).unwrap();
+    assert!(query.contains(
+        "make_array(named_struct('column_index', 0, 'value', CAST(\"id\" AS VARCHAR)), named_struct('column_index', 2, 'value', CAST(\"name\" AS VARCHAR)))"
+    ));
+    assert!(query.contains("FROM \"tbl\""));
+
+    let columns = vec![("tags".to_string(), "Struct(\"a\": Int64)".to_string())];
+    assert_eq!(None, build_top_values_query("tbl", &columns));
+}
+
+/// The name of the data type without its parameters, eg: Timestamp for Timestamp(Nanosecond, None)
+fn type_name(data_type: &str) -> &str {
+    data_type.split('(').next().unwrap_or_default().trim()
+}
+
+fn is_numeric(data_type: &str) -> bool {
+    matches!(
+        type_name(data_type),
+        "Int8"
+            | "Int16"
+            | "Int32"
+            | "Int64"
+            | "UInt8"
+            | "UInt16"
+            | "UInt32"
+            | "UInt64"
+            | "Float16"
+            | "Float32"
+            | "Float64"
+    ) || type_name(data_type).starts_with("Decimal")
+}
+
+fn supports_approx_distinct(data_type: &str) -> bool {
+    matches!(
+        type_name(data_type),
+        "Int8"
+            | "Int16"
+            | "Int32"
+            | "Int64"
+            | "UInt8"
+            | "UInt16"
+            | "UInt32"
+            | "UInt64"
+            | "Utf8"
+            | "LargeUtf8"
+            | "Utf8View"
+            | "Binary"
+            | "LargeBinary"
+            | "Date32"
+            | "Date64"
+            | "Time32"
+            | "Time64"
+            | "Timestamp"
+    )
+}
+
+/// Nested types (list, struct, map, ...) can not be grouped or ordered
+fn is_groupable(data_type: &str) -> bool {
+    is_numeric(data_type)
+        || matches!(
+            type_name(data_type),
+            "Boolean"
+                | "Utf8"
+                | "LargeUtf8"
+                | "Utf8View"
+                | "Date32"
+                | "Date64"
+                | "Time32"
+                | "Time64"
+                | "Timestamp"
+                | "Duration"
+        )
+}
+
+#[test]
+fn test_data_type_classification() {
+    assert!(is_numeric("Int64"));
+    assert!(is_numeric("Decimal128(10, 2)"));
+    assert!(!is_numeric("Interval(MonthDayNano)"));
+    assert!(supports_approx_distinct("Int32"));
+    assert!(supports_approx_distinct("Timestamp(Nanosecond, None)"));
+    assert!(!supports_approx_distinct("Float64"));
+    assert!(is_groupable("Float64"));
+    assert!(!is_groupable("Struct(\"a\": Int64)"));
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a ArrayRef> {
+    batch
+        .column_by_name(name)
+        .ok_or_else(|| DataFusionError::Execution(format!("Could not find column {name}")))
+}
+
+fn get_i64(batch: &RecordBatch, name: &str) -> Result<Option<i64>> {
+    let array = cast(column(batch, name)?, &DataType::Int64)?;
+    let array = as_int64_array(&array)?;
+    Ok(array.is_valid(0).then(|| array.value(0)))
+}
+
+fn get_f64(batch: &RecordBatch, name: &str) -> Result<Option<f64>> {
+    let array = cast(column(batch, name)?, &DataType::Float64)?;
+    let array = as_float64_array(&array)?;
+    Ok(array.is_valid(0).then(|| array.value(0)))
+}
+
+fn get_string(batch: &RecordBatch, name: &str) -> Result<Option<String>> {
+    let array = cast(column(batch, name)?, &DataType::Utf8)?;
+    let array = as_string_array(&array)?;
+    Ok(array.is_valid(0).then(|| array.value(0).to_string()))
+}
+
+fn to_record_batch(profiles: Vec<ColumnProfile>) -> Result<RecordBatch> {
+    let strings = |f: fn(&ColumnProfile) -> Option<String>| -> ArrayRef {
+        Arc::new(StringArray::from(
+            profiles.iter().map(f).collect::<Vec<_>>(),
+        ))
+    };
+    let integers = |f: fn(&ColumnProfile) -> Option<i64>| -> ArrayRef {
+        Arc::new(Int64Array::from(profiles.iter().map(f).collect::<Vec<_>>()))
+    };
+    let floats = |f: fn(&ColumnProfile) -> Option<f64>| -> ArrayRef {
+        Arc::new(Float64Array::from(
+            profiles.iter().map(f).collect::<Vec<_>>(),
+        ))
+    };
+
+    let batch = RecordBatch::try_from_iter(vec![
+        ("column_name", strings(|p| Some(p.column_name.clone()))),
+        ("data_type", strings(|p| Some(p.data_type.clone()))),
+        ("count", integers(|p| p.count)),
+        ("null_count", integers(|p| p.null_count)),
+        ("distinct_count", integers(|p| p.distinct_count)),
+        ("min", strings(|p| p.min.clone())),
+        ("max", strings(|p| p.max.clone())),
+        ("mean", floats(|p| p.mean)),
+        ("stddev", floats(|p| p.stddev)),
+        ("top_values", strings(|p| p.top_values.clone())),
+    ])?;
+    Ok(batch)
+}
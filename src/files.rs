@@ -0,0 +1,11 @@
+use datafusion::common::Result;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::prelude::SessionContext;
+
+/// Reads the complete file at the location from the object store registered for it
+pub async fn read_file(ctx: &SessionContext, location: &str) -> Result<Vec<u8>> {
+    let url = ListingTableUrl::parse(location)?;
+    let store = ctx.runtime_env().object_store(url.object_store())?;
+    let bytes = store.get(url.prefix()).await?.bytes().await?;
+    Ok(bytes.to_vec())
+}
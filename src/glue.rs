@@ -0,0 +1,887 @@
+use std::any::Any;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use aws_sdk_glue::error::SdkError;
+use aws_sdk_glue::operation::get_table::GetTableError;
+use aws_sdk_glue::types::{Column as GlueColumn, Database, Partition, Table};
+use aws_sdk_glue::Client;
+use aws_types::SdkConfig;
+use datafusion::arrow::array::{ArrayRef, StringArray, TimestampMillisecondArray};
+use datafusion::arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::catalog::{CatalogProvider, SchemaProvider, Session};
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::datasource::listing::{
+    ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+};
+use datafusion::datasource::TableProvider;
+use datafusion::execution::runtime_env::RuntimeEnv;
+use datafusion::logical_expr::expr::InList;
+use datafusion::logical_expr::{
+    BinaryExpr, Expr, Operator, TableProviderFilterPushDown, TableType,
+};
+use datafusion::physical_expr::expressions::{Column, Literal};
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::union::UnionExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::prelude::SessionContext;
+use url::Url;
+
+use crate::args::CatalogOptions;
+use crate::{
+    build_s3, build_table, create_session_context, lookup_delta_location, lookup_file_format,
+    lookup_iceberg_metadata_location, lookup_storage_location,
+};
+
+/// Value of partitions for which the value was null
+const HIVE_DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// A partitioned glue table. The partitions (each with their own location and format) are fetched
+/// from glue when the table is scanned, with the filters on the partition keys pushed down.
+#[derive(Debug)]
+pub struct GluePartitionedTable {
+    client: Client,
+    sdk_config: SdkConfig,
+    table: Table,
+    /// the columns of the data files followed by the partition keys
+    schema: SchemaRef,
+    file_schema: SchemaRef,
+    partition_cols: Vec<(String, DataType)>,
+}
+
+impl GluePartitionedTable {
+    /// The schema of the data files is taken from the columns of the table, or inferred from
+    /// the first partition when infer_schema is set (or the table has no columns)
+    pub async fn try_new(
+        ctx: &SessionContext,
+        sdk_config: &SdkConfig,
+        table: Table,
+        infer_schema: bool,
+    ) -> Result<Self> {
+        let client = Client::new(sdk_config);
+        let partition_cols = table
+            .partition_keys()
+            .iter()
+            .map(|column| {
+                Ok((
+                    column.name().to_string(),
+                    hive_type_to_arrow(column.r#type().unwrap_or("string"))?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let columns = table
+            .storage_descriptor()
+            .map(|sd| sd.columns())
+            .unwrap_or_default();
+        let file_schema = if infer_schema || columns.is_empty() {
+            Self::infer_file_schema(ctx, sdk_config, &client, &table).await?
+        } else {
+            Arc::new(glue_schema(columns)?)
+        };
+
+        let mut fields = file_schema
+            .fields()
+            .iter()
+            .map(|field| field.as_ref().clone())
+            .collect::<Vec<_>>();
+        for (name, data_type) in &partition_cols {
+            fields.push(Field::new(name, data_type.clone(), true));
+        }
+
+        Ok(Self {
+            client,
+            sdk_config: sdk_config.clone(),
+            table,
+            schema: Arc::new(Schema::new(fields)),
+            file_schema,
+            partition_cols,
+        })
+    }
+
+    async fn infer_file_schema(
+        ctx: &SessionContext,
+        sdk_config: &SdkConfig,
+        client: &Client,
+        table: &Table,
+    ) -> Result<SchemaRef> {
+        let first_partition = client
+            .get_partitions()
+            .set_database_name(table.database_name().map(String::from))
+            .table_name(table.name())
+            .max_results(1)
+            .send()
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .partitions()
+            .first()
+            .cloned();
+
+        match first_partition {
+            Some(partition) => {
+                let (table_url, options) = get_partition_location_and_options(table, &partition)?;
+                register_s3_store_when_needed(&ctx.runtime_env(), &table_url, sdk_config).await?;
+                options.infer_schema(&ctx.state(), &table_url).await
+            }
+            None => Ok(Arc::new(Schema::empty())),
+        }
+    }
+
+    async fn get_partitions(&self, expression: Option<String>) -> Result<Vec<Partition>> {
+        self.client
+            .get_partitions()
+            .set_database_name(self.table.database_name().map(String::from))
+            .table_name(self.table.name())
+            .set_expression(expression)
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))
+            .map(|pages| {
+                pages
+                    .into_iter()
+                    .flat_map(|page| page.partitions.unwrap_or_default())
+                    .collect()
+            })
+    }
+
+    fn get_partition_values(&self, partition: &Partition) -> Result<Vec<ScalarValue>> {
+        self.partition_cols
+            .iter()
+            .enumerate()
+            .map(|(i, (name, data_type))| match partition.values().get(i) {
+                Some(value) if value != HIVE_DEFAULT_PARTITION => {
+                    ScalarValue::try_from_string(value.clone(), data_type)
+                }
+                Some(_) => ScalarValue::try_from(data_type),
+                None => Err(DataFusionError::Execution(format!(
+                    "Could not find value for partition key {name} in {:?}",
+                    partition.values()
+                ))),
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl TableProvider for GluePartitionedTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let partition_keys = self
+            .partition_cols
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        let expressions = filters
+            .iter()
+            .filter_map(|filter| to_glue_expression(filter, &partition_keys))
+            .collect::<Vec<_>>();
+        let expression = (!expressions.is_empty()).then(|| expressions.join(" AND "));
+        let partitions = self.get_partitions(expression).await?;
+
+        let projection = projection
+            .cloned()
+            .unwrap_or_else(|| (0..self.schema.fields().len()).collect());
+        let num_file_columns = self.file_schema.fields().len();
+        let file_projection = projection
+            .iter()
+            .copied()
+            .filter(|i| *i < num_file_columns)
+            .collect::<Vec<_>>();
+
+        let mut plans: Vec<Arc<dyn ExecutionPlan>> = vec![];
+        for partition in partitions {
+            let (table_url, options) = get_partition_location_and_options(&self.table, &partition)?;
+            register_s3_store_when_needed(state.runtime_env(), &table_url, &self.sdk_config)
+                .await?;
+            let config = ListingTableConfig::new(table_url)
+                .with_listing_options(options)
+                .with_schema(self.file_schema.clone());
+            let plan = ListingTable::try_new(config)?
+                .scan(state, Some(&file_projection), &[], limit)
+                .await?;
+
+            // the file columns are taken from the scan, the partition keys are added as literals
+            let values = self.get_partition_values(&partition)?;
+            let mut file_column_index = 0;
+            let mut exprs: Vec<(Arc<dyn PhysicalExpr>, String)> = vec![];
+            for i in &projection {
+                let name = self.schema.field(*i).name().to_string();
+                if *i < num_file_columns {
+                    exprs.push((Arc::new(Column::new(&name, file_column_index)), name));
+                    file_column_index += 1;
+                } else {
+                    let value = values[*i - num_file_columns].clone();
+                    exprs.push((Arc::new(Literal::new(value)), name));
+                }
+            }
+            plans.push(Arc::new(ProjectionExec::try_new(exprs, plan)?));
+        }
+
+        match plans.len() {
+            0 => Ok(Arc::new(EmptyExec::new(Arc::new(
+                self.schema.project(&projection)?,
+            )))),
+            1 => Ok(plans.remove(0)),
+            _ => Ok(Arc::new(UnionExec::new(plans))),
+        }
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        // the filters are only used to select the partitions, datafusion still applies them
+        Ok(vec![TableProviderFilterPushDown::Inexact; filters.len()])
+    }
+}
+
+fn get_partition_location_and_options(
+    table: &Table,
+    partition: &Partition,
+) -> Result<(ListingTableUrl, ListingOptions)> {
+    let sd = partition.storage_descriptor().ok_or_else(|| {
+        DataFusionError::Execution(format!(
+            "Could not find storage descriptor for partition {:?} of {}",
+            partition.values(),
+            table.name()
+        ))
+    })?;
+    let location = lookup_storage_location(sd)?;
+    let table_url = ListingTableUrl::parse(format!("{}/", location.trim_end_matches('/')))?;
+    let format = lookup_file_format(table.clone(), sd)?;
+    Ok((
+        table_url,
+        ListingOptions::new(format).with_file_extension(""),
+    ))
+}
+
+/// Partitions can be stored in other buckets than the table
+async fn register_s3_store_when_needed(
+    runtime_env: &RuntimeEnv,
+    table_url: &ListingTableUrl,
+    sdk_config: &SdkConfig,
+) -> Result<()> {
+    if table_url.scheme() != "s3" || runtime_env.object_store(table_url.object_store()).is_ok() {
+        return Ok(());
+    }
+    let url = Url::parse(table_url.object_store().as_str())
+        .map_err(|e| DataFusionError::Execution(format!("Failed to parse url, {e}")))?;
+    let store = build_s3(&url, sdk_config).await?;
+    runtime_env.register_object_store(&url, Arc::new(store));
+    Ok(())
+}
+
+/// Maps the columns of (the storage descriptor of) a glue table to an arrow schema
+pub fn glue_schema(columns: &[GlueColumn]) -> Result<Schema> {
+    let fields = columns
+        .iter()
+        .map(|column| {
+            Ok(Field::new(
+                column.name(),
+                hive_type_to_arrow(column.r#type().unwrap_or("string"))?,
+                true,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Schema::new(fields))
+}
+
+/// Maps the hive type of a glue column to an arrow type, eg: array<struct<a:int,b:decimal(10,2)>>
+pub fn hive_type_to_arrow(hive_type: &str) -> Result<DataType> {
+    let hive_type = hive_type.trim();
+    let invalid_type =
+        || DataFusionError::Execution(format!("Failed to parse hive type {hive_type}"));
+
+    if let Some(element_type) = type_parameters(hive_type, "array", '<', '>') {
+        let element = Field::new("item", hive_type_to_arrow(element_type)?, true);
+        return Ok(DataType::List(Arc::new(element)));
+    }
+    if let Some(parameters) = type_parameters(hive_type, "map", '<', '>') {
+        let parameters = split_type_parameters(parameters);
+        let [key_type, value_type] = parameters[..] else {
+            return Err(invalid_type());
+        };
+        let entries = Fields::from(vec![
+            Field::new("key", hive_type_to_arrow(key_type)?, false),
+            Field::new("value", hive_type_to_arrow(value_type)?, true),
+        ]);
+        let entries = Field::new("entries", DataType::Struct(entries), false);
+        return Ok(DataType::Map(Arc::new(entries), false));
+    }
+    if let Some(parameters) = type_parameters(hive_type, "struct", '<', '>') {
+        let fields = split_type_parameters(parameters)
+            .into_iter()
+            .map(|field| {
+                let (name, field_type) = field.split_once(':').ok_or_else(invalid_type)?;
+                let name = name.trim().trim_matches('`');
+                Ok(Field::new(name, hive_type_to_arrow(field_type)?, true))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(DataType::Struct(Fields::from(fields)));
+    }
+    if let Some(parameters) = type_parameters(hive_type, "decimal", '(', ')') {
+        let (precision, scale) = parameters.split_once(',').ok_or_else(invalid_type)?;
+        let precision = precision.trim().parse().map_err(|_| invalid_type())?;
+        let scale = scale.trim().parse().map_err(|_| invalid_type())?;
+        return Ok(DataType::Decimal128(precision, scale));
+    }
+
+    let data_type = match hive_type.to_lowercase().as_str() {
+        "string" => DataType::Utf8,
+        "tinyint" => DataType::Int8,
+        "smallint" => DataType::Int16,
+        "int" | "integer" => DataType::Int32,
+        "bigint" => DataType::Int64,
+        "float" => DataType::Float32,
+        "double" => DataType::Float64,
+        // the default precision and scale of hive decimals
+        "decimal" => DataType::Decimal128(10, 0),
+        "boolean" => DataType::Boolean,
+        "binary" => DataType::Binary,
+        "date" => DataType::Date32,
+        "timestamp" => DataType::Timestamp(TimeUnit::Microsecond, None),
+        t if t.starts_with("varchar") || t.starts_with("char") => DataType::Utf8,
+        t => {
+            return Err(DataFusionError::Execution(format!(
+                "No support for hive type {t} yet."
+            )))
+        }
+    };
+    Ok(data_type)
+}
+
+#[test]
+fn test_hive_type_to_arrow() -> Result<()> {
+    assert_eq!(DataType::Utf8, hive_type_to_arrow("string")?);
+    assert_eq!(DataType::Int32, hive_type_to_arrow("INT")?);
+    assert_eq!(DataType::Utf8, hive_type_to_arrow("varchar(10)")?);
+    assert_eq!(DataType::Date32, hive_type_to_arrow("date")?);
+    assert_eq!(
+        DataType::Decimal128(12, 2),
+        hive_type_to_arrow("decimal(12, 2)")?
+    );
+    assert_eq!(
+        DataType::List(Arc::new(Field::new("item", DataType::Int64, true))),
+        hive_type_to_arrow("array<bigint>")?
+    );
+    assert_eq!(
+        DataType::Struct(Fields::from(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Decimal128(10, 2), true),
+            Field::new(
+                "c",
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                true
+            ),
+        ])),
+        hive_type_to_arrow("struct<a:int,b:decimal(10,2),c:array<string>>")?
+    );
+    let DataType::Map(entries, false) = hive_type_to_arrow("map<string,array<int>>")? else {
+        panic!("expected a map");
+    };
+    assert_eq!(
+        &DataType::Struct(Fields::from(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new(
+                "value",
+                DataType::List(Arc::new(Field::new("item", DataType::Int32, true))),
+                true
+            ),
+        ])),
+        entries.data_type()
+    );
+    assert!(hive_type_to_arrow("map<string>").is_err());
+    assert!(hive_type_to_arrow("uniontype<int,string>").is_err());
+    Ok(())
+}
+
+/// The parameters of a parameterized type, eg: int for array<int>
+fn type_parameters<'a>(hive_type: &'a str, name: &str, open: char, close: char) -> Option<&'a str> {
+    let prefix = hive_type.get(..name.len())?;
+    if !prefix.eq_ignore_ascii_case(name) {
+        return None;
+    }
+    hive_type[name.len()..]
+        .trim_start()
+        .strip_prefix(open)?
+        .strip_suffix(close)
+}
+
+/// Splits the parameters on the commas which are not nested in another type
+fn split_type_parameters(parameters: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in parameters.char_indices() {
+        match c {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(parameters[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(parameters[start..].trim());
+    parts
+}
+
+/// Returns the token glue uses for a comparison operator, eg: <> instead of !=
+fn to_glue_operator(op: &Operator) -> Option<&'static str> {
+    match op {
+        Operator::Eq => Some("="),
+        Operator::NotEq => Some("<>"),
+        Operator::Lt => Some("<"),
+        Operator::LtEq => Some("<="),
+        Operator::Gt => Some(">"),
+        Operator::GtEq => Some(">="),
+        _ => None,
+    }
+}
+
+/// Translates a filter on partition keys into a glue partition expression, eg: dt = '2024-01-01'.
+/// Returns None when (a part of) the filter can not be evaluated by glue.
+fn to_glue_expression(filter: &Expr, partition_keys: &[&str]) -> Option<String> {
+    match filter {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => match op {
+            // dropping an unsupported side of a conjunction selects more partitions, which is fine
+            Operator::And => match (
+                to_glue_expression(left, partition_keys),
+                to_glue_expression(right, partition_keys),
+            ) {
+                (Some(left), Some(right)) => Some(format!("({left}) AND ({right})")),
+                (left, right) => left.or(right),
+            },
+            Operator::Or => Some(format!(
+                "({}) OR ({})",
+                to_glue_expression(left, partition_keys)?,
+                to_glue_expression(right, partition_keys)?
+            )),
+            Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq => match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(column), Expr::Literal(value, _))
+                    if partition_keys.contains(&column.name.as_str()) =>
+                {
+                    Some(format!(
+                        "{} {} {}",
+                        column.name,
+                        to_glue_operator(op)?,
+                        to_glue_literal(value)?
+                    ))
+                }
+                (Expr::Literal(_, _), Expr::Column(_)) => {
+                    let swapped = BinaryExpr::new(right.clone(), op.swap()?, left.clone());
+                    to_glue_expression(&Expr::BinaryExpr(swapped), partition_keys)
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+        Expr::InList(InList {
+            expr,
+            list,
+            negated,
+        }) => match expr.as_ref() {
+            Expr::Column(column) if partition_keys.contains(&column.name.as_str()) => {
+                let values = list
+                    .iter()
+                    .map(|item| match item {
+                        Expr::Literal(value, _) => to_glue_literal(value),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                let not = if *negated { "NOT " } else { "" };
+                Some(format!("{} {not}IN ({})", column.name, values.join(", ")))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn to_glue_literal(value: &ScalarValue) -> Option<String> {
+    match value {
+        ScalarValue::Utf8(Some(s))
+        | ScalarValue::LargeUtf8(Some(s))
+        | ScalarValue::Utf8View(Some(s)) => Some(format!("'{}'", s.replace('\'', "''"))),
+        ScalarValue::Date32(Some(_)) => Some(format!("'{value}'")),
+        ScalarValue::Int8(Some(_))
+        | ScalarValue::Int16(Some(_))
+        | ScalarValue::Int32(Some(_))
+        | ScalarValue::Int64(Some(_))
+        | ScalarValue::UInt8(Some(_))
+        | ScalarValue::UInt16(Some(_))
+        | ScalarValue::UInt32(Some(_))
+        | ScalarValue::UInt64(Some(_)) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_to_glue_expression() {
+    use datafusion::prelude::{col, lit};
+
+    let partition_keys = ["dt", "region"];
+    assert_eq!(
+        Some("dt = '2024-01-01'".to_string()),
+        to_glue_expression(&col("dt").eq(lit("2024-01-01")), &partition_keys)
+    );
+    assert_eq!(
+        Some("dt >= '2024-01-01'".to_string()),
+        to_glue_expression(&lit("2024-01-01").lt_eq(col("dt")), &partition_keys)
+    );
+    assert_eq!(
+        Some("region <> 'eu'".to_string()),
+        to_glue_expression(&col("region").not_eq(lit("eu")), &partition_keys)
+    );
+    assert_eq!(
+        Some("region IN ('eu', 'us')".to_string()),
+        to_glue_expression(
+            &col("region").in_list(vec![lit("eu"), lit("us")], false),
+            &partition_keys
+        )
+    );
+    assert_eq!(
+        Some("(dt = 'x') AND (region = 'eu')".to_string()),
+        to_glue_expression(
+            &col("dt").eq(lit("x")).and(col("region").eq(lit("eu"))),
+            &partition_keys
+        )
+    );
+    assert_eq!(
+        Some("dt = 'x'".to_string()),
+        to_glue_expression(
+            &col("dt").eq(lit("x")).and(col("amount").gt(lit(5))),
+            &partition_keys
+        )
+    );
+    assert_eq!(
+        None,
+        to_glue_expression(
+            &col("dt").eq(lit("x")).or(col("amount").gt(lit(5))),
+            &partition_keys
+        )
+    );
+    assert_eq!(
+        None,
+        to_glue_expression(&col("amount").gt(lit(5)), &partition_keys)
+    );
+}
+
+async fn get_glue_databases(client: &Client) -> Result<Vec<Database>> {
+    client
+        .get_databases()
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))
+        .map(|pages| {
+            pages
+                .into_iter()
+                .flat_map(|page| page.database_list)
+                .collect()
+        })
+}
+
+async fn get_glue_tables(client: &Client, database_name: &str) -> Result<Vec<Table>> {
+    client
+        .get_tables()
+        .database_name(database_name)
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))
+        .map(|pages| {
+            pages
+                .into_iter()
+                .flat_map(|page| page.table_list.unwrap_or_default())
+                .collect()
+        })
+}
+
+/// Returns the databases in the glue catalog
+pub async fn list_glue_databases(sdk_config: &SdkConfig) -> Result<RecordBatch> {
+    let databases = get_glue_databases(&Client::new(sdk_config)).await?;
+
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "name",
+            Arc::new(StringArray::from_iter_values(
+                databases.iter().map(|database| database.name()),
+            )) as ArrayRef,
+        ),
+        (
+            "description",
+            Arc::new(StringArray::from_iter(
+                databases.iter().map(|database| database.description()),
+            )),
+        ),
+        (
+            "location",
+            Arc::new(StringArray::from_iter(
+                databases.iter().map(|database| database.location_uri()),
+            )),
+        ),
+        (
+            "create_time",
+            Arc::new(
+                TimestampMillisecondArray::from_iter(
+                    databases
+                        .iter()
+                        .map(|database| to_millis(database.create_time())),
+                )
+                .with_timezone("UTC"),
+            ),
+        ),
+    ])?;
+    Ok(batch)
+}
+
+/// Returns the tables of the glue database with their type, format and location
+pub async fn list_glue_tables(sdk_config: &SdkConfig, database_name: &str) -> Result<RecordBatch> {
+    let tables = get_glue_tables(&Client::new(sdk_config), database_name).await?;
+
+    let partition_keys = tables
+        .iter()
+        .map(|table| {
+            table
+                .partition_keys()
+                .iter()
+                .map(|column| column.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .collect::<Vec<_>>();
+
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "name",
+            Arc::new(StringArray::from_iter_values(
+                tables.iter().map(|table| table.name()),
+            )) as ArrayRef,
+        ),
+        (
+            "table_type",
+            Arc::new(StringArray::from_iter(tables.iter().map(get_table_type))),
+        ),
+        (
+            "format",
+            Arc::new(StringArray::from_iter(tables.iter().map(get_format_name))),
+        ),
+        (
+            "location",
+            Arc::new(StringArray::from_iter(tables.iter().map(|table| {
+                table.storage_descriptor().and_then(|sd| sd.location())
+            }))),
+        ),
+        (
+            "partition_keys",
+            Arc::new(StringArray::from_iter_values(partition_keys)),
+        ),
+        (
+            "update_time",
+            Arc::new(
+                TimestampMillisecondArray::from_iter(
+                    tables.iter().map(|table| to_millis(table.update_time())),
+                )
+                .with_timezone("UTC"),
+            ),
+        ),
+    ])?;
+    Ok(batch)
+}
+
+/// The table_type parameter (eg: ICEBERG), or else the type of the table (eg: EXTERNAL_TABLE)
+fn get_table_type(table: &Table) -> Option<String> {
+    table
+        .parameters()
+        .and_then(|parameters| parameters.get("table_type"))
+        .map(String::as_str)
+        .or(table.table_type())
+        .map(String::from)
+}
+
+/// The name of the format in which qv reads the table, or else the input format of the table
+fn get_format_name(table: &Table) -> Option<String> {
+    if lookup_iceberg_metadata_location(table).is_some() {
+        return Some("iceberg".to_string());
+    }
+    if lookup_delta_location(table).is_some() {
+        return Some("delta".to_string());
+    }
+    let sd = table.storage_descriptor()?;
+    match lookup_file_format(table.clone(), sd) {
+        Ok(format) => Some(format.get_ext().trim_start_matches('.').to_string()),
+        Err(_) => sd.input_format().map(String::from),
+    }
+}
+
+fn to_millis(date_time: Option<&aws_sdk_glue::primitives::DateTime>) -> Option<i64> {
+    date_time.and_then(|date_time| date_time.to_millis().ok())
+}
+
+/// The glue catalog as datafusion catalog, the databases are its schemas.
+/// Tables are resolved when they are used in a query, like glue://<database>.<table> paths.
+/// The catalog only holds the runtime (with the object stores), not the session it is registered in.
+pub struct GlueCatalog {
+    runtime: Arc<RuntimeEnv>,
+    options: CatalogOptions,
+    sdk_config: SdkConfig,
+}
+
+impl GlueCatalog {
+    pub fn new(runtime: Arc<RuntimeEnv>, options: CatalogOptions, sdk_config: &SdkConfig) -> Self {
+        Self {
+            runtime,
+            options,
+            sdk_config: sdk_config.clone(),
+        }
+    }
+}
+
+impl Debug for GlueCatalog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlueCatalog").finish_non_exhaustive()
+    }
+}
+
+impl CatalogProvider for GlueCatalog {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema_names(&self) -> Vec<String> {
+        let client = Client::new(&self.sdk_config);
+        match block_on(get_glue_databases(&client)) {
+            Ok(databases) => databases
+                .iter()
+                .map(|database| database.name().to_string())
+                .collect(),
+            Err(e) => {
+                eprintln!("Failed to list glue databases: {e}");
+                vec![]
+            }
+        }
+    }
+
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        Some(Arc::new(GlueSchema {
+            runtime: self.runtime.clone(),
+            options: self.options.clone(),
+            sdk_config: self.sdk_config.clone(),
+            database_name: name.to_string(),
+        }))
+    }
+}
+
+struct GlueSchema {
+    runtime: Arc<RuntimeEnv>,
+    options: CatalogOptions,
+    sdk_config: SdkConfig,
+    database_name: String,
+}
+
+impl Debug for GlueSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlueSchema")
+            .field("database_name", &self.database_name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl SchemaProvider for GlueSchema {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        let client = Client::new(&self.sdk_config);
+        match block_on(get_glue_tables(&client, &self.database_name)) {
+            Ok(tables) => tables
+                .iter()
+                .map(|table| table.name().to_string())
+                .collect(),
+            Err(e) => {
+                eprintln!(
+                    "Failed to list tables of glue database {}: {e}",
+                    self.database_name
+                );
+                vec![]
+            }
+        }
+    }
+
+    /// Unknown tables are None, tables which can not be resolved are an error
+    async fn table(&self, name: &str) -> Result<Option<Arc<dyn TableProvider>>> {
+        let path = format!("glue://{}.{name}", self.database_name);
+        let ctx = create_session_context(self.runtime.clone())?;
+        match build_table(&path, &self.options.to_args(), &self.sdk_config, &ctx).await {
+            Err(e) if is_entity_not_found(&e) => Ok(None),
+            result => result.map(Some),
+        }
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.table_names()
+            .iter()
+            .any(|table_name| table_name == name)
+    }
+}
+
+/// True when glue does not know the table (or its database)
+fn is_entity_not_found(e: &DataFusionError) -> bool {
+    match e {
+        DataFusionError::External(e) => e
+            .downcast_ref::<SdkError<GetTableError>>()
+            .and_then(SdkError::as_service_error)
+            .is_some_and(GetTableError::is_entity_not_found_exception),
+        _ => false,
+    }
+}
+
+#[test]
+fn test_is_entity_not_found() {
+    assert!(!is_entity_not_found(&DataFusionError::External(Box::new(
+        SdkError::<GetTableError>::timeout_error("timed out")
+    ))));
+    assert!(!is_entity_not_found(&DataFusionError::Execution(
+        "Could not find db.t in glue".to_string()
+    )));
+}
+
+/// Runs the future to completion from the synchronous parts of the catalog api
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
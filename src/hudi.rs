@@ -0,0 +1,321 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use chrono::{DateTime, Local, Utc};
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::{
+    ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+};
+use datafusion::datasource::TableProvider;
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+use object_store::path::Path;
+use object_store::ObjectMeta;
+use serde::Deserialize;
+
+use crate::files::read_file;
+
+/// An instant on the timeline of the table (.hoodie/ or .hoodie/timeline/ since hudi 1.0)
+#[derive(Debug, PartialEq)]
+struct Instant {
+    /// the (requested) instant time, eg: 20240101120000123
+    timestamp: String,
+    /// the action, followed by the state for instants which are not completed (eg: commit.inflight)
+    action: String,
+}
+
+impl Instant {
+    fn is_completed_commit(&self) -> bool {
+        self.action == "commit" || self.action == "replacecommit"
+    }
+}
+
+/// A base file is named <file id>_<write token>_<instant time>.parquet
+#[derive(Debug)]
+struct BaseFile {
+    partition_path: String,
+    file_id: String,
+    timestamp: String,
+    location: Path,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ReplaceCommitMetadata {
+    #[serde(default)]
+    partition_to_replace_file_ids: HashMap<String, Vec<String>>,
+}
+
+/// Builds a table with the latest committed base file (at the provided timestamp) of every file
+/// group when the provided path is a hudi copy-on-write table.
+/// Returns None when the path is not a hudi table.
+pub async fn open_hudi_table(
+    ctx: &SessionContext,
+    path: &str,
+    at: Option<DateTime<Utc>>,
+) -> Result<Option<Arc<dyn TableProvider>>> {
+    let base_path = path.trim_end_matches('/');
+    let Ok(properties) = read_file(ctx, &format!("{base_path}/.hoodie/hoodie.properties")).await
+    else {
+        return Ok(None);
+    };
+    let properties = String::from_utf8_lossy(&properties);
+    if let Some(table_type) = get_property(&properties, "hoodie.table.type") {
+        if table_type != "COPY_ON_WRITE" {
+            return Err(DataFusionError::Execution(format!(
+                "No support for hudi {table_type} tables yet."
+            )));
+        }
+    }
+
+    let base_url = ListingTableUrl::parse(format!("{base_path}/"))?;
+    let store = ctx.runtime_env().object_store(base_url.object_store())?;
+    let objects: Vec<ObjectMeta> = store.list(Some(base_url.prefix())).try_collect().await?;
+
+    let mut instants = vec![];
+    let mut base_files = vec![];
+    for object in &objects {
+        let Some(parts) = object.location.prefix_match(base_url.prefix()) else {
+            continue;
+        };
+        let parts = parts
+            .map(|part| part.as_ref().to_string())
+            .collect::<Vec<_>>();
+        let parts = parts.iter().map(String::as_str).collect::<Vec<_>>();
+        match parts.as_slice() {
+            [".hoodie", file_name] | [".hoodie", "timeline", file_name] => {
+                if let Some(instant) = parse_instant(file_name) {
+                    instants.push((instant, object.location.clone()));
+                }
+            }
+            // the metadata table, archived timeline, .hoodie_partition_metadata, ...
+            _ if parts.iter().any(|part| part.starts_with(['.', '_'])) => {}
+            [partition_path @ .., file_name] => {
+                if let Some((file_id, timestamp)) = parse_base_file_name(file_name) {
+                    base_files.push(BaseFile {
+                        partition_path: partition_path.join("/"),
+                        file_id: file_id.to_string(),
+                        timestamp: timestamp.to_string(),
+                        location: object.location.clone(),
+                    });
+                }
+            }
+            [] => {}
+        }
+    }
+
+    let timeline_timezone = get_property(&properties, "hoodie.table.timeline.timezone");
+    let at = at.map(|at| format_instant_time(at, timeline_timezone));
+    // instants are written with second (14 digits) or millisecond (17 digits) precision
+    let is_visible = |timestamp: &str| {
+        at.as_ref()
+            .is_none_or(|at| timestamp <= &at[..timestamp.len().min(at.len())])
+    };
+
+    let completed_commits = instants
+        .iter()
+        .filter(|(instant, _)| instant.is_completed_commit() && is_visible(&instant.timestamp))
+        .collect::<Vec<_>>();
+    let completed_timestamps = completed_commits
+        .iter()
+        .map(|(instant, _)| instant.timestamp.as_str())
+        .collect::<HashSet<_>>();
+    // instants before the active timeline were archived, which only happens for completed instants
+    let earliest_active_timestamp = instants
+        .iter()
+        .map(|(instant, _)| instant.timestamp.as_str())
+        .min();
+
+    // file groups which were replaced by clustering or insert overwrite
+    let mut replaced_file_groups = HashSet::new();
+    for (instant, location) in &completed_commits {
+        if instant.action == "replacecommit" {
+            let location = format!("{}{location}", base_url.object_store().as_str());
+            let metadata = read_file(ctx, &location).await?;
+            replaced_file_groups.extend(parse_replaced_file_groups(&metadata).map_err(|e| {
+                DataFusionError::Execution(format!("Failed to parse {location}: {e}"))
+            })?);
+        }
+    }
+
+    let mut latest_base_files: HashMap<(&str, &str), &BaseFile> = HashMap::new();
+    for base_file in &base_files {
+        let is_committed = completed_timestamps.contains(base_file.timestamp.as_str())
+            || earliest_active_timestamp
+                .is_some_and(|earliest| base_file.timestamp.as_str() < earliest);
+        let file_group = (
+            base_file.partition_path.as_str(),
+            base_file.file_id.as_str(),
+        );
+        if !is_committed
+            || !is_visible(&base_file.timestamp)
+            || replaced_file_groups.contains(&(file_group.0.to_string(), file_group.1.to_string()))
+        {
+            continue;
+        }
+        let latest = latest_base_files.entry(file_group).or_insert(base_file);
+        if base_file.timestamp > latest.timestamp {
+            *latest = base_file;
+        }
+    }
+
+    // the schema is taken from the most recent base file
+    let mut latest_base_files = latest_base_files.into_values().collect::<Vec<_>>();
+    latest_base_files.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    if latest_base_files.is_empty() {
+        return Err(DataFusionError::Execution(format!(
+            "Hudi table {path} has no committed base files"
+        )));
+    }
+
+    let table_paths = latest_base_files
+        .iter()
+        .map(|base_file| {
+            ListingTableUrl::parse(format!(
+                "{}{}",
+                base_url.object_store().as_str(),
+                base_file.location
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let options = ListingOptions::new(Arc::new(ParquetFormat::default())).with_file_extension("");
+    let schema = options.infer_schema(&ctx.state(), &table_paths[0]).await?;
+    let config = ListingTableConfig::new_with_multi_paths(table_paths)
+        .with_listing_options(options)
+        .with_schema(schema);
+    let table = ListingTable::try_new(config)?;
+    Ok(Some(Arc::new(table)))
+}
+
+fn get_property<'a>(properties: &'a str, key: &str) -> Option<&'a str> {
+    properties
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(name, _)| name.trim() == key)
+        .map(|(_, value)| value.trim())
+}
+
+#[test]
+fn test_get_property() {
+    let properties = "#Properties saved on 2024-01-01\nhoodie.table.name=trips\nhoodie.table.type=COPY_ON_WRITE\n";
+    assert_eq!(
+        Some("COPY_ON_WRITE"),
+        get_property(properties, "hoodie.table.type")
+    );
+    assert_eq!(None, get_property(properties, "hoodie.table.version"));
+}
+
+/// Instant times are written in the timezone of the timeline, LOCAL (of the writer) unless UTC is configured
+fn format_instant_time(at: DateTime<Utc>, timeline_timezone: Option<&str>) -> String {
+    let format = "%Y%m%d%H%M%S%3f";
+    if timeline_timezone.is_some_and(|timezone| timezone.eq_ignore_ascii_case("UTC")) {
+        at.format(format).to_string()
+    } else {
+        at.with_timezone(&Local).format(format).to_string()
+    }
+}
+
+#[test]
+fn test_format_instant_time() {
+    let at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00.123+01:00")
+        .unwrap()
+        .with_timezone(&Utc);
+    assert_eq!("20240101110000123", format_instant_time(at, Some("UTC")));
+    assert_eq!(
+        at.with_timezone(&Local)
+            .format("%Y%m%d%H%M%S%3f")
+            .to_string(),
+        format_instant_time(at, Some("LOCAL"))
+    );
+    assert_eq!(
+        format_instant_time(at, Some("LOCAL")),
+        format_instant_time(at, None)
+    );
+}
+
+/// Instants are named <instant time>.<action>[.<state>] or <requested time>_<completion time>.<action> (hudi 1.0)
+fn parse_instant(file_name: &str) -> Option<Instant> {
+    let (timestamp, action) = file_name.split_once('.')?;
+    let timestamp = timestamp.split('_').next()?;
+    if timestamp.is_empty() || !timestamp.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(Instant {
+        timestamp: timestamp.to_string(),
+        action: action.to_string(),
+    })
+}
+
+#[test]
+fn test_parse_instant() {
+    let instant = parse_instant("20240101120000123.commit").unwrap();
+    assert_eq!("20240101120000123", instant.timestamp);
+    assert!(instant.is_completed_commit());
+
+    let instant = parse_instant("20240101120000123_20240101120001456.replacecommit").unwrap();
+    assert_eq!("20240101120000123", instant.timestamp);
+    assert!(instant.is_completed_commit());
+
+    assert!(!parse_instant("20240101120000123.commit.requested")
+        .unwrap()
+        .is_completed_commit());
+    assert!(!parse_instant("20240101120000123.inflight")
+        .unwrap()
+        .is_completed_commit());
+    assert_eq!(None, parse_instant("hoodie.properties"));
+}
+
+fn parse_base_file_name(file_name: &str) -> Option<(&str, &str)> {
+    let name = file_name.strip_suffix(".parquet")?;
+    let (file_id, name) = name.split_once('_')?;
+    let (_, timestamp) = name.rsplit_once('_')?;
+    Some((file_id, timestamp))
+}
+
+#[test]
+fn test_parse_base_file_name() {
+    assert_eq!(
+        Some((
+            "8c2a9b4e-1d3f-4e5a-9b6c-7d8e9f0a1b2c-0",
+            "20240101120000123"
+        )),
+        parse_base_file_name(
+            "8c2a9b4e-1d3f-4e5a-9b6c-7d8e9f0a1b2c-0_0-10-20_20240101120000123.parquet"
+        )
+    );
+    assert_eq!(None, parse_base_file_name(".hoodie_partition_metadata"));
+    assert_eq!(None, parse_base_file_name("data.parquet"));
+}
+
+/// Returns the (partition path, file id) of the file groups replaced by a replacecommit
+fn parse_replaced_file_groups(
+    metadata: &[u8],
+) -> serde_json::Result<impl Iterator<Item = (String, String)>> {
+    let metadata: ReplaceCommitMetadata = serde_json::from_slice(metadata)?;
+    Ok(metadata
+        .partition_to_replace_file_ids
+        .into_iter()
+        .flat_map(|(partition_path, file_ids)| {
+            file_ids
+                .into_iter()
+                .map(move |file_id| (partition_path.clone(), file_id))
+        }))
+}
+
+#[test]
+fn test_parse_replaced_file_groups() {
+    let metadata = br#"{"partitionToReplaceFileIds": {"region=eu": ["a-0", "b-0"]}, "operationType": "CLUSTER"}"#;
+    let mut file_groups = parse_replaced_file_groups(metadata)
+        .unwrap()
+        .collect::<Vec<_>>();
+    file_groups.sort();
+    assert_eq!(
+        vec![
+            ("region=eu".to_string(), "a-0".to_string()),
+            ("region=eu".to_string(), "b-0".to_string())
+        ],
+        file_groups
+    );
+}
@@ -0,0 +1,630 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use datafusion::arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::empty::EmptyTable;
+use datafusion::datasource::file_format::avro::AvroFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::{
+    ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+};
+use datafusion::datasource::TableProvider;
+use datafusion::prelude::SessionContext;
+use datafusion_orc::OrcFormat;
+use futures::TryStreamExt;
+use object_store::ObjectMeta;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::files::read_file;
+use crate::orc::with_stripe_pruning;
+
+/// The parts of the iceberg table metadata (metadata/*.metadata.json) which are needed to find the data files and schema
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct TableMetadata {
+    location: String,
+    current_snapshot_id: Option<i64>,
+    #[serde(default)]
+    snapshots: Vec<Snapshot>,
+    current_schema_id: Option<i32>,
+    #[serde(default)]
+    schemas: Vec<IcebergSchema>,
+    /// the only schema of v1 tables which were written before schemas was introduced
+    schema: Option<IcebergSchema>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct Snapshot {
+    snapshot_id: i64,
+    timestamp_ms: i64,
+    manifest_list: Option<String>,
+    /// the schema which was current when the snapshot was written
+    schema_id: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct IcebergSchema {
+    #[serde(default)]
+    schema_id: i32,
+    fields: Vec<IcebergField>,
+}
+
+#[derive(Deserialize, Debug)]
+struct IcebergField {
+    id: i32,
+    name: String,
+    required: bool,
+    #[serde(rename = "type")]
+    field_type: IcebergType,
+}
+
+/// Primitive types are strings (eg: long, decimal(10,2)), nested types are objects
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum IcebergType {
+    Primitive(String),
+    Nested(NestedType),
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum NestedType {
+    Struct {
+        fields: Vec<IcebergField>,
+    },
+    List {
+        element: Box<IcebergType>,
+        #[serde(rename = "element-required")]
+        element_required: bool,
+    },
+    Map {
+        key: Box<IcebergType>,
+        value: Box<IcebergType>,
+        #[serde(rename = "value-required")]
+        value_required: bool,
+    },
+}
+
+/// Entry of the manifest list (avro)
+#[derive(Deserialize, Debug)]
+struct ManifestFile {
+    manifest_path: String,
+    /// 0: data, 1: deletes (only present in v2 tables)
+    #[serde(default)]
+    content: i32,
+}
+
+/// Entry of a manifest (avro)
+#[derive(Deserialize, Debug)]
+struct ManifestEntry {
+    /// 0: existing, 1: added, 2: deleted
+    status: i32,
+    data_file: DataFile,
+}
+
+#[derive(Deserialize, Debug)]
+struct DataFile {
+    /// 0: data, 1: position deletes, 2: equality deletes (only present in v2 tables)
+    #[serde(default)]
+    content: i32,
+    file_path: String,
+    file_format: String,
+}
+
+/// Builds a table with the data files of the current snapshot (or the snapshot at the provided
+/// timestamp or with the provided id) when the provided path is an iceberg table (or metadata file).
+/// Returns None when the path is not an iceberg table.
+pub async fn open_iceberg_table(
+    ctx: &SessionContext,
+    path: &str,
+    at: Option<DateTime<Utc>>,
+    snapshot_id: Option<i64>,
+) -> Result<Option<Arc<dyn TableProvider>>> {
+    let metadata_location = if path.ends_with(".metadata.json") {
+        path.to_string()
+    } else {
+        match find_metadata_location(ctx, path).await {
+            Some(metadata_location) => metadata_location,
+            None => return Ok(None),
+        }
+    };
+
+    let metadata: TableMetadata =
+        serde_json::from_slice(&read_file(ctx, &metadata_location).await?).map_err(|e| {
+            DataFusionError::Execution(format!("Failed to parse {metadata_location}: {e}"))
+        })?;
+
+    // an empty table (without snapshots or data files) has no rows
+    let Some(snapshot) = select_snapshot(&metadata, at, snapshot_id)? else {
+        let schema = iceberg_schema(&metadata, None)?;
+        return Ok(Some(Arc::new(EmptyTable::new(schema))));
+    };
+    // time travel reads a snapshot with the schema it was written with, otherwise the current schema
+    // is used (which includes the columns that were added after the current snapshot was written)
+    let schema_id = if at.is_some() || snapshot_id.is_some() {
+        snapshot.schema_id
+    } else {
+        None
+    };
+    let schema = iceberg_schema(&metadata, schema_id)?;
+
+    let manifest_list = snapshot.manifest_list.as_ref().ok_or_else(|| {
+        DataFusionError::Execution(format!(
+            "Snapshot {} of iceberg table {path} has no manifest list",
+            snapshot.snapshot_id
+        ))
+    })?;
+
+    // data files may have been written with another location (eg: a copied warehouse)
+    let table_location = metadata_location
+        .rsplit_once("/metadata/")
+        .map(|(table_location, _)| table_location)
+        .unwrap_or(&metadata.location);
+    let relocate_path = |file_path: &str| relocate(file_path, &metadata.location, table_location);
+
+    let manifest_files: Vec<ManifestFile> =
+        read_avro_file(ctx, &relocate_path(manifest_list)).await?;
+    let mut data_files = vec![];
+    for manifest_file in manifest_files {
+        let entries: Vec<ManifestEntry> =
+            read_avro_file(ctx, &relocate_path(&manifest_file.manifest_path)).await?;
+        for entry in entries.into_iter().filter(|entry| entry.status != 2) {
+            if manifest_file.content != 0 || entry.data_file.content != 0 {
+                return Err(DataFusionError::Execution(format!(
+                    "No support for iceberg delete files yet: {}",
+                    entry.data_file.file_path
+                )));
+            }
+            data_files.push(entry.data_file);
+        }
+    }
+
+    let Some(file_format) = get_file_format(&data_files)? else {
+        return Ok(Some(Arc::new(EmptyTable::new(schema))));
+    };
+
+    check_renamed_columns(&metadata)?;
+
+    let table_paths = data_files
+        .iter()
+        .map(|data_file| ListingTableUrl::parse(relocate_path(&data_file.file_path)))
+        .collect::<Result<Vec<_>>>()?;
+
+    // files which were written before a column was added are read with nulls for that column
+    let options = ListingOptions::new(file_format).with_file_extension("");
+    let config = ListingTableConfig::new_with_multi_paths(table_paths)
+        .with_listing_options(options)
+        .with_schema(schema);
+    let table = ListingTable::try_new(config)?;
+    Ok(Some(with_stripe_pruning(table)))
+}
+
+/// The arrow schema with the provided id, the current schema when no id is provided
+fn iceberg_schema(metadata: &TableMetadata, schema_id: Option<i32>) -> Result<SchemaRef> {
+    let schema_id = schema_id.or(metadata.current_schema_id);
+    let schema = metadata
+        .schemas
+        .iter()
+        .find(|schema| Some(schema.schema_id) == schema_id)
+        .or(metadata.schema.as_ref())
+        .or(metadata.schemas.last())
+        .ok_or_else(|| {
+            DataFusionError::Execution(format!(
+                "Could not find schema {} in the iceberg metadata",
+                schema_id.unwrap_or_default()
+            ))
+        })?;
+    Ok(Arc::new(Schema::new(iceberg_fields(&schema.fields)?)))
+}
+
+fn iceberg_fields(fields: &[IcebergField]) -> Result<Fields> {
+    fields
+        .iter()
+        .map(|field| {
+            Ok(Field::new(
+                &field.name,
+                iceberg_type_to_arrow(&field.field_type)?,
+                !field.required,
+            ))
+        })
+        .collect()
+}
+
+fn iceberg_type_to_arrow(iceberg_type: &IcebergType) -> Result<DataType> {
+    let data_type = match iceberg_type {
+        IcebergType::Nested(NestedType::Struct { fields }) => {
+            DataType::Struct(iceberg_fields(fields)?)
+        }
+        IcebergType::Nested(NestedType::List {
+            element,
+            element_required,
+        }) => DataType::List(Arc::new(Field::new(
+            "element",
+            iceberg_type_to_arrow(element)?,
+            !element_required,
+        ))),
+        IcebergType::Nested(NestedType::Map {
+            key,
+            value,
+            value_required,
+        }) => {
+            let entries = Fields::from(vec![
+                Field::new("key", iceberg_type_to_arrow(key)?, false),
+                Field::new("value", iceberg_type_to_arrow(value)?, !value_required),
+            ]);
+            DataType::Map(
+                Arc::new(Field::new("key_value", DataType::Struct(entries), false)),
+                false,
+            )
+        }
+        IcebergType::Primitive(primitive) => match primitive.as_str() {
+            "boolean" => DataType::Boolean,
+            "int" => DataType::Int32,
+            "long" => DataType::Int64,
+            "float" => DataType::Float32,
+            "double" => DataType::Float64,
+            "date" => DataType::Date32,
+            "time" => DataType::Time64(TimeUnit::Microsecond),
+            "timestamp" => DataType::Timestamp(TimeUnit::Microsecond, None),
+            "timestamptz" => DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into())),
+            "timestamp_ns" => DataType::Timestamp(TimeUnit::Nanosecond, None),
+            "timestamptz_ns" => DataType::Timestamp(TimeUnit::Nanosecond, Some("+00:00".into())),
+            "string" => DataType::Utf8,
+            "uuid" => DataType::FixedSizeBinary(16),
+            "binary" => DataType::Binary,
+            other => parse_parameterized_type(other).ok_or_else(|| {
+                DataFusionError::Execution(format!("No support for iceberg type {other} yet."))
+            })?,
+        },
+    };
+    Ok(data_type)
+}
+
+/// The data files are read by column name (not by field id), which reads columns that were renamed
+/// (or dropped and added again with the same name) wrong. Returns an error for such tables.
+fn check_renamed_columns(metadata: &TableMetadata) -> Result<()> {
+    let mut names_by_id: HashMap<i32, String> = HashMap::new();
+    let mut ids_by_name: HashMap<String, i32> = HashMap::new();
+    for schema in metadata.schemas.iter().chain(&metadata.schema) {
+        let mut columns = vec![];
+        collect_columns(&schema.fields, "", &mut columns);
+        for (id, name) in columns {
+            if let Some(other_name) = names_by_id.insert(id, name.clone()) {
+                if other_name != name {
+                    return Err(DataFusionError::Execution(format!(
+                        "No support for iceberg tables with renamed columns yet: {other_name} was renamed to {name}"
+                    )));
+                }
+            }
+            if let Some(other_id) = ids_by_name.insert(name.clone(), id) {
+                if other_id != id {
+                    return Err(DataFusionError::Execution(format!(
+                        "No support for iceberg tables with renamed columns yet: {name} was dropped and added again"
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The (field id, name) of the columns, nested columns are named by their path, eg: address.city
+fn collect_columns(fields: &[IcebergField], prefix: &str, columns: &mut Vec<(i32, String)>) {
+    for field in fields {
+        let name = format!("{prefix}{}", field.name);
+        let mut field_type = &field.field_type;
+        // the elements of lists and the values of maps can be structs as well
+        loop {
+            match field_type {
+                IcebergType::Nested(NestedType::List { element, .. }) => field_type = element,
+                IcebergType::Nested(NestedType::Map { value, .. }) => field_type = value,
+                _ => break,
+            }
+        }
+        if let IcebergType::Nested(NestedType::Struct { fields }) = field_type {
+            collect_columns(fields, &format!("{name}."), columns);
+        }
+        columns.push((field.id, name));
+    }
+}
+
+#[test]
+fn test_check_renamed_columns() {
+    let metadata = |schemas: &str| -> TableMetadata {
+        serde_json::from_str(&format!(
+            r#"{{"location": "s3://bucket/db/tbl", "schemas": [{schemas}]}}"#
+        ))
+        .unwrap()
+    };
+    let added_column = metadata(
+        r#"{"schema-id": 0, "fields": [{"id": 1, "name": "id", "required": true, "type": "long"}]},
+           {"schema-id": 1, "fields": [
+               {"id": 1, "name": "id", "required": true, "type": "long"},
+               {"id": 2, "name": "address", "required": false, "type": {"type": "struct", "fields": [{"id": 3, "name": "city", "required": false, "type": "string"}]}}
+           ]}"#,
+    );
+    assert!(check_renamed_columns(&added_column).is_ok());
+
+    let renamed_column = metadata(
+        r#"{"schema-id": 0, "fields": [{"id": 1, "name": "amount", "required": false, "type": "long"}]},
+           {"schema-id": 1, "fields": [{"id": 1, "name": "total", "required": false, "type": "long"}]}"#,
+    );
+    assert!(check_renamed_columns(&renamed_column).is_err());
+
+    let renamed_nested_column = metadata(
+        r#"{"schema-id": 0, "fields": [{"id": 1, "name": "address", "required": false, "type": {"type": "struct", "fields": [{"id": 2, "name": "city", "required": false, "type": "string"}]}}]},
+           {"schema-id": 1, "fields": [{"id": 1, "name": "address", "required": false, "type": {"type": "struct", "fields": [{"id": 2, "name": "town", "required": false, "type": "string"}]}}]}"#,
+    );
+    assert!(check_renamed_columns(&renamed_nested_column).is_err());
+
+    let added_again = metadata(
+        r#"{"schema-id": 0, "fields": [{"id": 1, "name": "amount", "required": false, "type": "long"}]},
+           {"schema-id": 1, "fields": [{"id": 2, "name": "amount", "required": false, "type": "long"}]}"#,
+    );
+    assert!(check_renamed_columns(&added_again).is_err());
+}
+
+/// Parses decimal(precision, scale) and fixed[length]
+fn parse_parameterized_type(iceberg_type: &str) -> Option<DataType> {
+    if let Some(parameters) = iceberg_type
+        .strip_prefix("decimal(")
+        .and_then(|parameters| parameters.strip_suffix(')'))
+    {
+        let (precision, scale) = parameters.split_once(',')?;
+        return Some(DataType::Decimal128(
+            precision.trim().parse().ok()?,
+            scale.trim().parse().ok()?,
+        ));
+    }
+    let length = iceberg_type
+        .strip_prefix("fixed[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()?;
+    Some(DataType::FixedSizeBinary(length))
+}
+
+#[test]
+fn test_iceberg_schema() -> Result<()> {
+    let metadata: TableMetadata = serde_json::from_str(
+        r#"{
+            "location": "s3://bucket/db/tbl",
+            "current-snapshot-id": -1,
+            "current-schema-id": 1,
+            "schemas": [
+                {"schema-id": 0, "fields": [{"id": 1, "name": "id", "required": true, "type": "int"}]},
+                {"schema-id": 1, "fields": [
+                    {"id": 1, "name": "id", "required": true, "type": "long"},
+                    {"id": 2, "name": "amount", "required": false, "type": "decimal(10, 2)"},
+                    {"id": 3, "name": "tags", "required": false, "type": {"type": "list", "element-id": 5, "element": "string", "element-required": false}},
+                    {"id": 4, "name": "address", "required": false, "type": {"type": "struct", "fields": [{"id": 6, "name": "city", "required": false, "type": "string"}]}},
+                    {"id": 7, "name": "created", "required": false, "type": "timestamptz"}
+                ]}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let schema = iceberg_schema(&metadata, None)?;
+    assert_eq!(5, schema.fields().len());
+    assert_eq!(&DataType::Int64, schema.field(0).data_type());
+    assert!(!schema.field(0).is_nullable());
+    assert_eq!(&DataType::Decimal128(10, 2), schema.field(1).data_type());
+    assert_eq!(
+        &DataType::List(Arc::new(Field::new("element", DataType::Utf8, true))),
+        schema.field(2).data_type()
+    );
+    assert_eq!(
+        &DataType::Struct(Fields::from(vec![Field::new("city", DataType::Utf8, true)])),
+        schema.field(3).data_type()
+    );
+
+    // a snapshot is read with the schema it was written with
+    let schema = iceberg_schema(&metadata, Some(0))?;
+    assert_eq!(&DataType::Int32, schema.field(0).data_type());
+
+    assert!(select_snapshot(&metadata, None, None)?.is_none());
+    Ok(())
+}
+
+/// Finds the metadata file with the highest version in the metadata folder of the table
+async fn find_metadata_location(ctx: &SessionContext, path: &str) -> Option<String> {
+    let metadata_url =
+        ListingTableUrl::parse(format!("{}/metadata/", path.trim_end_matches('/'))).ok()?;
+    let store = ctx
+        .runtime_env()
+        .object_store(metadata_url.object_store())
+        .ok()?;
+    let objects: Vec<ObjectMeta> = store
+        .list(Some(metadata_url.prefix()))
+        .try_collect()
+        .await
+        .ok()?;
+
+    objects
+        .iter()
+        .filter_map(|object| {
+            let file_name = object.location.filename()?;
+            parse_metadata_version(file_name).map(|version| (version, object))
+        })
+        .max_by_key(|(version, _)| *version)
+        .map(|(_, object)| {
+            format!(
+                "{}{}",
+                metadata_url.object_store().as_str(),
+                object.location
+            )
+        })
+}
+
+/// Metadata files are named v<version>.metadata.json or <version>-<uuid>.metadata.json
+fn parse_metadata_version(file_name: &str) -> Option<u64> {
+    let name = file_name.strip_suffix(".metadata.json")?;
+    name.trim_start_matches('v')
+        .split(['-', '.'])
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[test]
+fn test_parse_metadata_version() {
+    assert_eq!(Some(3), parse_metadata_version("v3.metadata.json"));
+    assert_eq!(
+        Some(12),
+        parse_metadata_version("00012-8b0a1d5c-a3f0-4b1c-9e1a-1d2b3c4d5e6f.metadata.json")
+    );
+    assert_eq!(None, parse_metadata_version("version-hint.text"));
+    assert_eq!(None, parse_metadata_version("snap-123.avro"));
+}
+
+fn select_snapshot(
+    metadata: &TableMetadata,
+    at: Option<DateTime<Utc>>,
+    snapshot_id: Option<i64>,
+) -> Result<Option<&Snapshot>> {
+    if let Some(snapshot_id) = snapshot_id {
+        let snapshot = metadata
+            .snapshots
+            .iter()
+            .find(|snapshot| snapshot.snapshot_id == snapshot_id)
+            .ok_or_else(|| {
+                let available = metadata
+                    .snapshots
+                    .iter()
+                    .map(|snapshot| snapshot.snapshot_id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                DataFusionError::Execution(format!(
+                    "Could not find snapshot {snapshot_id}, available snapshots: {available}"
+                ))
+            })?;
+        return Ok(Some(snapshot));
+    }
+
+    if let Some(at) = at {
+        let snapshot = metadata
+            .snapshots
+            .iter()
+            .filter(|snapshot| snapshot.timestamp_ms <= at.timestamp_millis())
+            .max_by_key(|snapshot| snapshot.timestamp_ms)
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!("Could not find a snapshot at or before {at}"))
+            })?;
+        return Ok(Some(snapshot));
+    }
+
+    // v1 tables use -1 when there is no current snapshot
+    Ok(metadata
+        .current_snapshot_id
+        .and_then(|current_snapshot_id| {
+            metadata
+                .snapshots
+                .iter()
+                .find(|snapshot| snapshot.snapshot_id == current_snapshot_id)
+        }))
+}
+
+#[test]
+fn test_select_snapshot() -> Result<()> {
+    let metadata: TableMetadata = serde_json::from_str(
+        r#"{
+            "location": "s3://bucket/db/tbl",
+            "current-snapshot-id": 2,
+            "snapshots": [
+                {"snapshot-id": 1, "timestamp-ms": 1000, "manifest-list": "s3://bucket/db/tbl/metadata/snap-1.avro"},
+                {"snapshot-id": 2, "timestamp-ms": 2000, "manifest-list": "s3://bucket/db/tbl/metadata/snap-2.avro"}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let current = select_snapshot(&metadata, None, None)?.unwrap();
+    assert_eq!(2, current.snapshot_id);
+
+    let by_id = select_snapshot(&metadata, None, Some(1))?.unwrap();
+    assert_eq!(1, by_id.snapshot_id);
+
+    let at = DateTime::from_timestamp_millis(1500).unwrap();
+    let by_timestamp = select_snapshot(&metadata, Some(at), None)?.unwrap();
+    assert_eq!(1, by_timestamp.snapshot_id);
+
+    assert!(select_snapshot(&metadata, None, Some(3)).is_err());
+    let before_first = DateTime::from_timestamp_millis(500).unwrap();
+    assert!(select_snapshot(&metadata, Some(before_first), None).is_err());
+    Ok(())
+}
+
+/// Replaces the location recorded in the metadata with the location the table was found at
+fn relocate(file_path: &str, metadata_location: &str, table_location: &str) -> String {
+    match file_path.strip_prefix(metadata_location.trim_end_matches('/')) {
+        Some(relative_path) => format!("{}{}", table_location.trim_end_matches('/'), relative_path),
+        None => file_path.to_string(),
+    }
+}
+
+#[test]
+fn test_relocate() {
+    assert_eq!(
+        "file:///data/iceberg/db/tbl/data/00000-0.parquet",
+        relocate(
+            "/home/iceberg/warehouse/db/tbl/data/00000-0.parquet",
+            "/home/iceberg/warehouse/db/tbl",
+            "file:///data/iceberg/db/tbl"
+        )
+    );
+    assert_eq!(
+        "s3://other/data/00000-0.parquet",
+        relocate(
+            "s3://other/data/00000-0.parquet",
+            "s3://bucket/db/tbl",
+            "s3://bucket/db/tbl"
+        )
+    );
+}
+
+fn get_file_format(data_files: &[DataFile]) -> Result<Option<Arc<dyn FileFormat>>> {
+    let Some(first) = data_files.first() else {
+        return Ok(None);
+    };
+    if let Some(other) = data_files.iter().find(|data_file| {
+        !data_file
+            .file_format
+            .eq_ignore_ascii_case(&first.file_format)
+    }) {
+        return Err(DataFusionError::Execution(format!(
+            "No support for iceberg tables with mixed file formats yet: {}, {}",
+            first.file_format, other.file_format
+        )));
+    }
+    match first.file_format.to_uppercase().as_str() {
+        "PARQUET" => Ok(Some(Arc::new(ParquetFormat::default()))),
+        "AVRO" => Ok(Some(Arc::new(AvroFormat))),
+        "ORC" => Ok(Some(Arc::new(OrcFormat))),
+        file_format => Err(DataFusionError::Execution(format!(
+            "No support for iceberg {file_format} data files yet."
+        ))),
+    }
+}
+
+async fn read_avro_file<T: DeserializeOwned>(
+    ctx: &SessionContext,
+    location: &str,
+) -> Result<Vec<T>> {
+    let bytes = read_file(ctx, location).await?;
+    let reader =
+        apache_avro::Reader::new(&bytes[..]).map_err(|e| DataFusionError::External(Box::new(e)))?;
+    reader
+        .map(|value| {
+            value
+                .and_then(|value| apache_avro::from_value::<T>(&value))
+                .map_err(|e| DataFusionError::External(Box::new(e)))
+        })
+        .collect()
+}
@@ -0,0 +1,263 @@
+use std::sync::Arc;
+
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::file_format::arrow::ArrowFormat;
+use datafusion::datasource::file_format::avro::AvroFormat;
+use datafusion::datasource::file_format::csv::CsvFormat;
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use datafusion::datasource::file_format::json::JsonFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::prelude::SessionContext;
+use datafusion_orc::OrcFormat;
+use futures::TryStreamExt;
+use object_store::ObjectMeta;
+
+use crate::args::{Args, InputFormat};
+use crate::arrow_ipc::{is_arrow_file, is_arrow_stream};
+use crate::spreadsheet::is_spreadsheet_data;
+
+/// Builds the file format with the compression and the csv and json options provided in the args.
+/// Spreadsheets are loaded in memory (see open_spreadsheet_table) and have no file format.
+pub fn build_file_format(
+    input_format: InputFormat,
+    compression: FileCompressionType,
+    args: &Args,
+) -> Result<Arc<dyn FileFormat>> {
+    let format: Arc<dyn FileFormat> = match input_format {
+        InputFormat::Parquet => Arc::new(ParquetFormat::default()),
+        InputFormat::Avro => Arc::new(AvroFormat),
+        InputFormat::Orc => Arc::new(OrcFormat),
+        InputFormat::Arrow => Arc::new(ArrowFormat),
+        InputFormat::Csv => {
+            Arc::new(build_csv_format(args).with_file_compression_type(compression))
+        }
+        InputFormat::Json => {
+            let format = JsonFormat::default().with_file_compression_type(compression);
+            match args.schema_infer_max_records {
+                Some(max_records) => Arc::new(format.with_schema_infer_max_rec(max_records)),
+                None => Arc::new(format),
+            }
+        }
+        InputFormat::Spreadsheet => {
+            return Err(DataFusionError::Execution(
+                "Spreadsheets can not be read as listing table".to_string(),
+            ))
+        }
+    };
+    Ok(format)
+}
+
+fn build_csv_format(args: &Args) -> CsvFormat {
+    let mut format = CsvFormat::default()
+        .with_has_header(!args.csv_no_header)
+        .with_escape(args.csv_escape)
+        .with_comment(args.csv_comment)
+        // the regex has to match the complete value, not a part of it
+        .with_null_regex(
+            args.csv_null_value
+                .as_ref()
+                .map(|null_value| format!("^{}$", regex::escape(null_value))),
+        );
+    if let Some(delimiter) = args.csv_delimiter {
+        format = format.with_delimiter(delimiter);
+    }
+    if let Some(quote) = args.csv_quote {
+        format = format.with_quote(quote);
+    }
+    if let Some(max_records) = args.schema_infer_max_records {
+        format = format.with_schema_infer_max_rec(max_records);
+    }
+    format
+}
+
+/// Detects the format from the first bytes of the data
+pub fn detect_input_format(bytes: &[u8]) -> InputFormat {
+    if bytes.starts_with(b"PAR1") {
+        InputFormat::Parquet
+    } else if bytes.starts_with(b"Obj\x01") {
+        InputFormat::Avro
+    } else if is_arrow_file(bytes) || is_arrow_stream(bytes) {
+        InputFormat::Arrow
+    } else if is_spreadsheet_data(bytes) {
+        InputFormat::Spreadsheet
+    } else if bytes.starts_with(b"ORC") && !bytes.get(3).is_some_and(u8::is_ascii_alphanumeric) {
+        InputFormat::Orc
+    } else if bytes
+        .iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .is_some_and(|byte| *byte == b'{')
+        || is_json_array(bytes)
+    {
+        InputFormat::Json
+    } else {
+        InputFormat::Csv
+    }
+}
+
+/// Returns true when the data starts with a json array, eg: [{"a": 1}, {"a": 2}]
+pub fn is_json_array(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .is_some_and(|byte| *byte == b'[')
+}
+
+#[test]
+fn test_detect_input_format() {
+    assert_eq!(InputFormat::Parquet, detect_input_format(b"PAR1\x15\x04"));
+    assert_eq!(InputFormat::Avro, detect_input_format(b"Obj\x01\x04\x14"));
+    assert_eq!(InputFormat::Orc, detect_input_format(b"ORC\x0a\x06"));
+    assert_eq!(
+        InputFormat::Spreadsheet,
+        detect_input_format(b"PK\x03\x04\x14\x00")
+    );
+    assert_eq!(
+        InputFormat::Arrow,
+        detect_input_format(b"ARROW1\x00\x00\xff\xff")
+    );
+    assert_eq!(
+        InputFormat::Arrow,
+        detect_input_format(b"\xff\xff\xff\xff\x78\x00")
+    );
+    assert_eq!(
+        InputFormat::Csv,
+        detect_input_format(b"ORCID,name\n1,foo\n")
+    );
+    assert_eq!(
+        InputFormat::Json,
+        detect_input_format(b"\n {\"a\": 1}\n{\"a\": 2}")
+    );
+    assert_eq!(
+        InputFormat::Json,
+        detect_input_format(b" [{\"a\": 1}, {\"a\": 2}]")
+    );
+    assert_eq!(InputFormat::Csv, detect_input_format(b"a,b\n1,2\n"));
+}
+
+/// Detects the compression from the extension of the first data file found at the path, eg: data.csv.gz
+pub async fn detect_file_compression(
+    ctx: &SessionContext,
+    data_path: &str,
+) -> Result<FileCompressionType> {
+    let compression = find_first_data_file(ctx, data_path)
+        .await?
+        .map(|object| compression_from_file_name(object.location.filename().unwrap_or_default()))
+        .unwrap_or(FileCompressionType::UNCOMPRESSED);
+    Ok(compression)
+}
+
+/// Returns the first file at the path which is not hidden (eg: _SUCCESS or .crc files)
+pub async fn find_first_data_file(
+    ctx: &SessionContext,
+    data_path: &str,
+) -> Result<Option<ObjectMeta>> {
+    let table_url = ListingTableUrl::parse(data_path)?;
+    let store = ctx.runtime_env().object_store(table_url.object_store())?;
+    let state = ctx.state();
+    let mut objects = table_url.list_all_files(&state, store.as_ref(), "").await?;
+    while let Some(object) = objects.try_next().await? {
+        let file_name = object.location.filename().unwrap_or_default();
+        if !file_name.starts_with(['_', '.']) {
+            return Ok(Some(object));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the compression of a file based on its extension, eg: data.csv.gz
+pub fn compression_from_file_name(file_name: &str) -> FileCompressionType {
+    file_name
+        .rsplit_once('.')
+        .and_then(|(_, extension)| extension.parse().ok())
+        .unwrap_or(FileCompressionType::UNCOMPRESSED)
+}
+
+#[test]
+fn test_compression_from_file_name() {
+    assert_eq!(
+        FileCompressionType::GZIP,
+        compression_from_file_name("data.csv.gz")
+    );
+    assert_eq!(
+        FileCompressionType::ZSTD,
+        compression_from_file_name("data.json.zst")
+    );
+    assert_eq!(
+        FileCompressionType::BZIP2,
+        compression_from_file_name("data.csv.bz2")
+    );
+    assert_eq!(
+        FileCompressionType::XZ,
+        compression_from_file_name("data.csv.xz")
+    );
+    assert_eq!(
+        FileCompressionType::UNCOMPRESSED,
+        compression_from_file_name("data.csv")
+    );
+    assert_eq!(
+        FileCompressionType::UNCOMPRESSED,
+        compression_from_file_name("part-00000")
+    );
+}
+
+/// Detects the compression from the magic bytes at the start of the data
+pub fn detect_compression(bytes: &[u8]) -> FileCompressionType {
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        FileCompressionType::GZIP
+    } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        FileCompressionType::ZSTD
+    } else if bytes.starts_with(b"BZh") {
+        FileCompressionType::BZIP2
+    } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        FileCompressionType::XZ
+    } else {
+        FileCompressionType::UNCOMPRESSED
+    }
+}
+
+#[test]
+fn test_detect_compression() {
+    assert_eq!(
+        FileCompressionType::GZIP,
+        detect_compression(&[0x1f, 0x8b, 0x08, 0x00])
+    );
+    assert_eq!(
+        FileCompressionType::ZSTD,
+        detect_compression(&[0x28, 0xb5, 0x2f, 0xfd, 0x04])
+    );
+    assert_eq!(FileCompressionType::BZIP2, detect_compression(b"BZh91AY"));
+    assert_eq!(
+        FileCompressionType::XZ,
+        detect_compression(b"\xfd7zXZ\x00\x00")
+    );
+    assert_eq!(
+        FileCompressionType::UNCOMPRESSED,
+        detect_compression(b"a,b\n1,2\n")
+    );
+}
+
+/// Parses the compressionType parameter of a glue table, eg: gzip or none
+pub fn parse_compression_type(compression_type: &str) -> Result<FileCompressionType> {
+    if compression_type.eq_ignore_ascii_case("none") {
+        return Ok(FileCompressionType::UNCOMPRESSED);
+    }
+    compression_type.parse().map_err(|_| {
+        DataFusionError::Execution(format!(
+            "No support for compression type {compression_type} yet."
+        ))
+    })
+}
+
+#[test]
+fn test_parse_compression_type() -> Result<()> {
+    assert_eq!(FileCompressionType::GZIP, parse_compression_type("gzip")?);
+    assert_eq!(FileCompressionType::ZSTD, parse_compression_type("zstd")?);
+    assert_eq!(
+        FileCompressionType::UNCOMPRESSED,
+        parse_compression_type("none")?
+    );
+    assert!(parse_compression_type("lzo").is_err());
+    Ok(())
+}
@@ -10,120 +10,378 @@ use aws_sdk_glue::types::{StorageDescriptor, Table};
 use aws_sdk_glue::Client;
 use aws_types::SdkConfig;
 use clap::Parser;
+use datafusion::arrow::datatypes::{DataType, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::common::{DataFusionError, Result};
 use datafusion::datasource::file_format::avro::AvroFormat;
 use datafusion::datasource::file_format::csv::CsvFormat;
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
 use datafusion::datasource::file_format::json::JsonFormat;
 use datafusion::datasource::file_format::parquet::ParquetFormat;
 use datafusion::datasource::file_format::FileFormat;
 use datafusion::datasource::listing::{
     ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
 };
-use datafusion::datasource::TableProvider;
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::execution::object_store::{DefaultObjectStoreRegistry, ObjectStoreRegistry};
+use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
 use datafusion::prelude::*;
 use datafusion::sql::TableReference;
-use deltalake::open_table;
+use datafusion_orc::OrcFormat;
+use deltalake::{open_table, DeltaTable};
+use http::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
 use object_store::aws::{AmazonS3, AmazonS3Builder};
+use object_store::azure::{MicrosoftAzure, MicrosoftAzureBuilder};
 use object_store::gcp::{GoogleCloudStorage, GoogleCloudStorageBuilder};
+use object_store::http::{HttpBuilder, HttpStore};
 use object_store::path::Path;
-use object_store::ObjectStore;
+use object_store::{ClientOptions, ObjectStore};
 use regex::Regex;
 use url::Url;
 
-use crate::args::Args;
+use crate::args::{Args, InputFormat};
+use crate::arrow_ipc::open_arrow_ipc_table;
+use crate::delta::{build_change_data_feed, get_details, get_history, load_delta_version};
+use crate::describe::describe_table;
+use crate::glue::{
+    glue_schema, list_glue_databases, list_glue_tables, GlueCatalog, GluePartitionedTable,
+};
+use crate::hudi::open_hudi_table;
+use crate::iceberg::open_iceberg_table;
+use crate::input_format::{build_file_format, detect_file_compression, parse_compression_type};
+use crate::orc::{with_stripe_pruning, OrcFormatFactory};
+use crate::output::write_output;
+use crate::parquet_metadata::build_parquet_metadata_table;
+use crate::partitions::infer_partition_cols;
+use crate::print::show_dataframe;
+use crate::spreadsheet::{get_sheets, open_spreadsheet_table};
+use crate::stdin::build_stdin_table;
+use crate::transcode::build_transcoded_table;
 
 mod args;
+mod arrow_ipc;
+mod delta;
+mod describe;
+mod files;
+mod glue;
+mod hudi;
+mod iceberg;
+mod input_format;
+mod orc;
+mod output;
+mod parquet_metadata;
+mod partitions;
+mod print;
+mod repl;
+mod spreadsheet;
+mod stdin;
+mod transcode;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = SessionConfig::new().with_information_schema(true);
-    let ctx = SessionContext::new_with_config(config);
+    let runtime = RuntimeEnvBuilder::new()
+        .with_object_store_registry(Arc::new(ObjectStoreRegistryByContainer::default()))
+        .build_arc()?;
+    let ctx = create_session_context(runtime)?;
 
     let args: Args = Args::parse();
 
-    let (_, data_path) = replace_s3_console_url_with_s3_path(&args.path.clone());
-
     let sdk_config = get_sdk_config(&args).await;
 
-    let (data_path, file_format) = replace_glue_table_with_path(&data_path, &sdk_config).await?;
+    if args.glue_catalog {
+        let catalog = GlueCatalog::new(ctx.runtime_env(), args.get_catalog_options(), &sdk_config);
+        ctx.register_catalog("glue", Arc::new(catalog));
+    }
 
-    let data_path = if data_path.starts_with("s3://") {
-        // register s3 object store
-        let s3_url = Url::parse(&data_path)
-            .map_err(|e| DataFusionError::Execution(format!("Failed to parse url, {e}")))?;
-        let s3 = build_s3(&s3_url, &sdk_config).await?;
-        let s3_arc = Arc::new(s3);
-        ctx.runtime_env()
-            .register_object_store(&s3_url, s3_arc.clone());
+    for (table_name, path) in args.get_table_paths() {
+        let table = build_table(&path, &args, &sdk_config, &ctx).await?;
+        let table_ref = TableReference::full("datafusion", "public", table_name.as_str());
+        if ctx.register_table(table_ref, table)?.is_some() {
+            return Err(DataFusionError::Execution(format!(
+                "Table {table_name} is provided more than once"
+            )));
+        }
+    }
 
-        deltalake::aws::register_handlers(None);
+    if args.is_interactive() {
+        return repl::run(&ctx, args.limit, args.format, !args.no_header).await;
+    }
 
-        // add trailing slash to folder
-        if !data_path.ends_with('/') {
-            let path = Path::parse(s3_url.path())?;
-            if s3_arc.head(&path).await.is_err() {
-                format!("{data_path}/")
-            } else {
-                data_path
-            }
+    let df = if args.describe {
+        let (table_name, _) = &args.get_table_paths()[0];
+        ctx.read_batch(describe_table(&ctx, table_name).await?)?
+    } else if args.history || args.details {
+        let (table_name, _) = &args.get_table_paths()[0];
+        let table = ctx.table_provider(table_name.as_str()).await?;
+        let delta_table = table
+            .as_any()
+            .downcast_ref::<DeltaTable>()
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "--history and --details are only supported for delta tables, {table_name} is not a delta table"
+                ))
+            })?;
+        let batch = if args.history {
+            get_history(&ctx, delta_table).await?
         } else {
-            data_path
-        }
+            get_details(delta_table)?
+        };
+        ctx.read_batch(batch)?
+    } else if let Some(sheets) = get_spreadsheet_sheets(&ctx, &args).await? {
+        ctx.read_batch(sheets)?
     } else {
-        data_path
+        let query = &args.get_query();
+        ctx.sql(query).await?
     };
+    if let Some(output) = &args.output {
+        let output = &replace_azure_https_url_with_az_path(output);
+        register_object_store(&ctx, output, &args, &sdk_config).await?;
+        let rows =
+            write_output(df, output, args.output_format, args.compression.as_deref()).await?;
+        eprintln!("Wrote {rows} rows to {output}");
+    } else {
+        let limit = if args.schema || args.describe || args.details {
+            None
+        } else {
+            Some(args.limit)
+        };
+        show_dataframe(df, limit, args.format, !args.no_header).await?;
+    }
 
-    let data_path = if data_path.starts_with("gs://") || data_path.starts_with("gcs://") {
-        let gcs_url = Url::parse(&data_path)
-            .map_err(|e| DataFusionError::Execution(format!("Failed to parse url, {e}")))?;
-        let gcs = build_gcs(&gcs_url).await?;
-        let gcs_arc = Arc::new(gcs);
-        ctx.runtime_env()
-            .register_object_store(&gcs_url, gcs_arc.clone());
+    Ok(())
+}
 
-        deltalake::gcp::register_handlers(None);
+/// With --schema the sheets of spreadsheets are listed instead of their columns
+async fn get_spreadsheet_sheets(ctx: &SessionContext, args: &Args) -> Result<Option<RecordBatch>> {
+    if !args.schema {
+        return Ok(None);
+    }
+    let table_names = args
+        .get_table_paths()
+        .into_iter()
+        .map(|(table_name, _)| table_name)
+        .collect::<Vec<_>>();
+    get_sheets(ctx, &table_names).await
+}
 
-        // add trailing slash to folder
-        if !data_path.ends_with('/') {
-            let path = Path::parse(gcs_url.path())?;
-            if gcs_arc.head(&path).await.is_err() {
-                format!("{data_path}/")
-            } else {
-                data_path
-            }
+/// Creates a session on the runtime (which holds the registered object stores)
+fn create_session_context(runtime: Arc<RuntimeEnv>) -> Result<SessionContext> {
+    let config = SessionConfig::new().with_information_schema(true);
+    let ctx = SessionContext::new_with_config_rt(config, runtime);
+    // datafusion does not know .orc files by default
+    ctx.state_ref()
+        .write()
+        .register_file_format(Arc::new(OrcFormatFactory), false)?;
+    Ok(ctx)
+}
+
+/// Resolves the provided path (s3 console url, glue table, object store, delta, iceberg or hudi table, spreadsheet, arrow ipc or other files)
+/// into a table provider
+async fn build_table(
+    path: &str,
+    args: &Args,
+    sdk_config: &SdkConfig,
+    ctx: &SessionContext,
+) -> Result<Arc<dyn TableProvider>> {
+    if path == "-" {
+        return build_stdin_table(ctx, args).await;
+    }
+
+    let (_, data_path) = replace_s3_console_url_with_s3_path(path);
+    let data_path = replace_azure_https_url_with_az_path(&data_path);
+
+    if let Some(database_name) = parse_glue_database_url(&data_path) {
+        let batch = if database_name.is_empty() {
+            list_glue_databases(sdk_config).await?
         } else {
-            data_path
+            list_glue_tables(sdk_config, &database_name).await?
+        };
+        return Ok(Arc::new(MemTable::try_new(
+            batch.schema(),
+            vec![vec![batch]],
+        )?));
+    }
+
+    let (data_path, file_format, schema, glue_table_kind) = match parse_glue_url(&data_path) {
+        Some((database_name, table_name)) => {
+            let table = get_glue_table(sdk_config, &database_name, &table_name).await?;
+            if is_glue_partitioned_table(&table) {
+                let table =
+                    GluePartitionedTable::try_new(ctx, sdk_config, table, args.infer_schema)
+                        .await?;
+                return Ok(Arc::new(table));
+            }
+            let (location, file_format) = get_path_and_format(&table)?;
+            // delta and iceberg tables have their own schema, only listing tables take the glue schema
+            let schema = if file_format.is_some() && !args.infer_schema {
+                lookup_glue_schema(&table)?
+            } else {
+                None
+            };
+            (
+                location,
+                file_format,
+                schema,
+                Some(get_glue_table_kind(&table)),
+            )
         }
-    } else {
-        data_path
+        None => (data_path, None, None, None),
+    };
+
+    let data_path = match register_object_store(ctx, &data_path, args, sdk_config).await? {
+        Some((url, store)) => add_trailing_slash_to_folder(data_path, &url, &store).await?,
+        None => data_path,
     };
 
     let data_path = normalize_data_path(&data_path)?;
 
-    let table: Arc<dyn TableProvider> = if let Some(delta_url) = parse_as_url(&data_path) {
-        if let Ok(mut delta_table) = open_table(delta_url).await {
-            if let Some(at) = args.at {
-                delta_table.load_with_datetime(at).await?;
+    if args.parquet_meta {
+        return build_parquet_metadata_table(ctx, &data_path).await;
+    }
+
+    if let Some(delta_url) = parse_as_url(&data_path) {
+        match open_table(delta_url).await {
+            Ok(mut delta_table) => {
+                if let Some(cdf_from) = args.cdf_from {
+                    return build_change_data_feed(delta_table, cdf_from, args.cdf_to);
+                }
+                if let Some(at) = args.at {
+                    delta_table.load_with_datetime(at).await?;
+                }
+                if let Some(version) = args.table_version {
+                    load_delta_version(ctx, &mut delta_table, &data_path, version).await?;
+                }
+                return Ok(Arc::new(delta_table));
             }
-            Arc::new(delta_table)
-        } else {
-            build_listing_table(&data_path, file_format, &ctx).await?
+            // the files of a delta table from glue should not be read as a listing table
+            Err(e) if glue_table_kind == Some(GlueTableKind::Delta) => return Err(e.into()),
+            Err(_) => {}
+        }
+    }
+
+    if args.cdf_from.is_some() {
+        return Err(DataFusionError::Execution(format!(
+            "--cdf-from is only supported for delta tables, {path} is not a delta table"
+        )));
+    }
+    if args.table_version.is_some() {
+        return Err(DataFusionError::Execution(format!(
+            "--table-version is only supported for delta tables, {path} is not a delta table"
+        )));
+    }
+
+    if let Some(iceberg_table) =
+        open_iceberg_table(ctx, &data_path, args.at, args.snapshot_id).await?
+    {
+        return Ok(iceberg_table);
+    }
+    if glue_table_kind == Some(GlueTableKind::Iceberg) {
+        return Err(DataFusionError::Execution(format!(
+            "Could not open iceberg table {path} at {data_path}"
+        )));
+    }
+    if args.snapshot_id.is_some() {
+        return Err(DataFusionError::Execution(format!(
+            "--snapshot-id is only supported for iceberg tables, {path} is not an iceberg table"
+        )));
+    }
+
+    if let Some(hudi_table) = open_hudi_table(ctx, &data_path, args.at).await? {
+        return Ok(hudi_table);
+    }
+
+    // spreadsheets and arrow ipc streams can not be read by a listing table
+    let input_format = args.get_input_format();
+    if file_format.is_none() && input_format.is_none_or(|f| f == InputFormat::Spreadsheet) {
+        if let Some(spreadsheet_table) =
+            open_spreadsheet_table(ctx, &data_path, input_format.is_some(), args).await?
+        {
+            return Ok(spreadsheet_table);
+        }
+    }
+    if file_format.is_none() && input_format.is_none_or(|f| f == InputFormat::Arrow) {
+        if let Some(arrow_table) = open_arrow_ipc_table(
+            ctx,
+            &data_path,
+            input_format.is_some(),
+            &args.partition_cols,
+        )
+        .await?
+        {
+            return Ok(arrow_table);
+        }
+    }
+
+    if args.needs_transcoding() {
+        return build_transcoded_table(ctx, &data_path, schema, args).await;
+    }
+
+    // an explicitly provided input format takes precedence over the format of the glue table
+    let file_format = match input_format {
+        Some(input_format) => {
+            let compression = detect_file_compression(ctx, &data_path).await?;
+            Some(build_file_format(input_format, compression, args)?)
         }
+        None => file_format,
+    };
+
+    // with an explicit format the files do not need a matching extension (eg: part-00000)
+    let options = file_format.map(|format| ListingOptions::new(format).with_file_extension(""));
+    build_listing_table(&data_path, options, schema, &args.partition_cols, ctx).await
+}
+
+/// Registers an object store with the runtime when the provided path is an s3://, gs://, az:// or http(s):// url.
+/// Returns the url and store which were registered, None for paths which do not need a store.
+async fn register_object_store(
+    ctx: &SessionContext,
+    path: &str,
+    args: &Args,
+    sdk_config: &SdkConfig,
+) -> Result<Option<(Url, Arc<dyn ObjectStore>)>> {
+    let is_s3 = path.starts_with("s3://");
+    let is_gcs = path.starts_with("gs://") || path.starts_with("gcs://");
+    let is_azure = ["az://", "abfs://", "abfss://"]
+        .iter()
+        .any(|scheme| path.starts_with(scheme));
+    let is_http = path.starts_with("http://") || path.starts_with("https://");
+    if !is_s3 && !is_gcs && !is_azure && !is_http {
+        return Ok(None);
+    }
+
+    let url = Url::parse(path)
+        .map_err(|e| DataFusionError::Execution(format!("Failed to parse url, {e}")))?;
+    let store: Arc<dyn ObjectStore> = if is_s3 {
+        deltalake::aws::register_handlers(None);
+        Arc::new(build_s3(&url, sdk_config).await?)
+    } else if is_gcs {
+        deltalake::gcp::register_handlers(None);
+        Arc::new(build_gcs(&url).await?)
+    } else if is_azure {
+        deltalake::azure::register_handlers(None);
+        Arc::new(build_azure(&url)?)
     } else {
-        build_listing_table(&data_path, file_format, &ctx).await?
+        Arc::new(build_http(&url, args)?)
     };
 
-    ctx.register_table(TableReference::from("datafusion.public.tbl"), table)?;
+    ctx.runtime_env().register_object_store(&url, store.clone());
+    Ok(Some((url, store)))
+}
 
-    let query = &args.get_query();
-    let df = ctx.sql(query).await?;
-    if args.schema {
-        df.show().await?;
+/// When the provided path is not an existing object, it is a folder and needs a trailing slash
+async fn add_trailing_slash_to_folder(
+    data_path: String,
+    url: &Url,
+    store: &Arc<dyn ObjectStore>,
+) -> Result<String> {
+    if data_path.ends_with('/') {
+        return Ok(data_path);
+    }
+    let path = Path::parse(url.path())?;
+    if store.head(&path).await.is_err() {
+        Ok(format!("{data_path}/"))
     } else {
-        df.show_limit(args.limit).await?;
+        Ok(data_path)
     }
-
-    Ok(())
 }
 
 async fn get_sdk_config(args: &Args) -> SdkConfig {
@@ -146,6 +404,88 @@ fn set_aws_region_when_needed() {
     }
 }
 
+/// When the provided s looks like https://<account>.blob.core.windows.net/<container>/<path>
+/// return abfss://<container>@<account>.dfs.core.windows.net/<path>, which names both the account and the
+/// container (for the object store and for delta tables).
+fn replace_azure_https_url_with_az_path(s: &str) -> String {
+    let re: Regex =
+        Regex::new(r"^https://([a-z0-9]+)\.(?:blob|dfs)\.core\.windows\.net/([^/]+)(/.*)?$")
+            .unwrap();
+    match re.captures(s) {
+        Some(captures) => format!(
+            "abfss://{}@{}.dfs.core.windows.net{}",
+            &captures[2],
+            &captures[1],
+            captures.get(3).map_or("/", |path| path.as_str())
+        ),
+        None => s.to_string(),
+    }
+}
+
+#[test]
+fn test_replace_azure_https_url_with_az_path() {
+    assert_eq!(
+        "az://container/data",
+        replace_azure_https_url_with_az_path("az://container/data")
+    );
+    assert_eq!(
+        "abfss://container@myaccount.dfs.core.windows.net/data/file.parquet",
+        replace_azure_https_url_with_az_path(
+            "https://myaccount.blob.core.windows.net/container/data/file.parquet"
+        )
+    );
+    assert_eq!(
+        "abfss://container@myaccount.dfs.core.windows.net/",
+        replace_azure_https_url_with_az_path("https://myaccount.dfs.core.windows.net/container")
+    );
+}
+
+/// DataFusion registers object stores per scheme and host, abfss://<container>@<account>.dfs.core.windows.net
+/// urls have the container in the user info, so the container is moved into the host of the key.
+/// Otherwise all containers of an account would share the store which was registered last.
+#[derive(Debug, Default)]
+struct ObjectStoreRegistryByContainer {
+    inner: DefaultObjectStoreRegistry,
+}
+
+impl ObjectStoreRegistryByContainer {
+    fn key(url: &Url) -> Url {
+        let mut key = url.clone();
+        if let Some(host) = url.host_str().filter(|_| !url.username().is_empty()) {
+            let host = format!("{}.{host}", url.username());
+            if key.set_host(Some(&host)).is_ok() {
+                let _ = key.set_username("");
+            }
+        }
+        key
+    }
+}
+
+impl ObjectStoreRegistry for ObjectStoreRegistryByContainer {
+    fn register_store(
+        &self,
+        url: &Url,
+        store: Arc<dyn ObjectStore>,
+    ) -> Option<Arc<dyn ObjectStore>> {
+        self.inner.register_store(&Self::key(url), store)
+    }
+
+    fn get_store(&self, url: &Url) -> Result<Arc<dyn ObjectStore>> {
+        self.inner.get_store(&Self::key(url))
+    }
+}
+
+#[test]
+fn test_object_store_registry_key() {
+    let key =
+        |url: &str| ObjectStoreRegistryByContainer::key(&Url::parse(url).unwrap()).to_string();
+    assert_eq!(
+        "abfss://container.myaccount.dfs.core.windows.net/data",
+        key("abfss://container@myaccount.dfs.core.windows.net/data")
+    );
+    assert_eq!("s3://bucket/data", key("s3://bucket/data"));
+}
+
 /// When the provided s looks like an https url from the amazon webui convert it to an s3:// url
 /// When the provided s does not like such url, return it as is.
 fn replace_s3_console_url_with_s3_path(s: &str) -> (bool, String) {
@@ -189,18 +529,6 @@ fn test_replace_s3_console_url_with_s3_path() -> Result<()> {
     Ok(())
 }
 
-async fn replace_glue_table_with_path(
-    path: &str,
-    sdk_config: &SdkConfig,
-) -> Result<(String, Option<Arc<dyn FileFormat>>)> {
-    if let Some((database, table)) = parse_glue_url(path) {
-        let (location, format) = get_path_and_format(sdk_config, &database, &table).await?;
-        Ok((location, Some(format)))
-    } else {
-        Ok((String::from(path), None))
-    }
-}
-
 fn parse_glue_url(s: &str) -> Option<(String, String)> {
     let re: Regex = Regex::new(r"^glue://(\w+)\.(\w+)$").unwrap();
     re.captures(s).map(|captures| {
@@ -219,13 +547,30 @@ fn test_parse_glue_url() {
     );
 }
 
-async fn get_path_and_format(
+/// glue:// lists the databases (returned as an empty name), glue://db lists the tables of db
+fn parse_glue_database_url(s: &str) -> Option<String> {
+    let re: Regex = Regex::new(r"^glue://(\w*)/?$").unwrap();
+    re.captures(s).map(|captures| captures[1].to_string())
+}
+
+#[test]
+fn test_parse_glue_database_url() {
+    assert_eq!(Some("".to_string()), parse_glue_database_url("glue://"));
+    assert_eq!(Some("db".to_string()), parse_glue_database_url("glue://db"));
+    assert_eq!(
+        Some("db".to_string()),
+        parse_glue_database_url("glue://db/")
+    );
+    assert_eq!(None, parse_glue_database_url("glue://db.table"));
+}
+
+async fn get_glue_table(
     sdk_config: &SdkConfig,
     database_name: &str,
     table_name: &str,
-) -> Result<(String, Arc<dyn FileFormat>)> {
+) -> Result<Table> {
     let client: Client = Client::new(sdk_config);
-    let table = client
+    client
         .get_table()
         .set_database_name(Some(database_name.to_string()))
         .set_name(Some(table_name.to_string()))
@@ -238,18 +583,163 @@ async fn get_path_and_format(
                 "Could not find {}.{} in glue",
                 database_name, table_name
             ))
-        })?;
+        })
+}
+
+/// How a glue table is read
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GlueTableKind {
+    Iceberg,
+    Delta,
+    Listing,
+}
+
+fn get_glue_table_kind(table: &Table) -> GlueTableKind {
+    if lookup_iceberg_metadata_location(table).is_some() {
+        GlueTableKind::Iceberg
+    } else if lookup_delta_location(table).is_some() {
+        GlueTableKind::Delta
+    } else {
+        GlueTableKind::Listing
+    }
+}
+
+#[test]
+fn test_get_glue_table_kind() {
+    let table = |parameters: &[(&str, &str)]| {
+        parameters
+            .iter()
+            .fold(Table::builder().name("t"), |builder, (key, value)| {
+                builder.parameters(*key, *value)
+            })
+            .storage_descriptor(
+                StorageDescriptor::builder()
+                    .location("s3a://bucket/t")
+                    .build(),
+            )
+            .build()
+            .unwrap()
+    };
+    assert_eq!(
+        GlueTableKind::Iceberg,
+        get_glue_table_kind(&table(&[
+            ("table_type", "ICEBERG"),
+            (
+                "metadata_location",
+                "s3://bucket/t/metadata/v1.metadata.json"
+            )
+        ]))
+    );
+    assert_eq!(
+        GlueTableKind::Delta,
+        get_glue_table_kind(&table(&[("spark.sql.sources.provider", "delta")]))
+    );
+    assert_eq!(
+        GlueTableKind::Listing,
+        get_glue_table_kind(&table(&[("classification", "parquet")]))
+    );
+}
+
+/// Partitions of glue tables can be stored at any location, iceberg and delta tables manage their own partitions
+fn is_glue_partitioned_table(table: &Table) -> bool {
+    !table.partition_keys().is_empty()
+        && lookup_iceberg_metadata_location(table).is_none()
+        && lookup_delta_location(table).is_none()
+}
+
+fn get_path_and_format(table: &Table) -> Result<(String, Option<Arc<dyn FileFormat>>)> {
+    // iceberg tables have no input/output format, the data files are found via the metadata
+    if let Some(metadata_location) = lookup_iceberg_metadata_location(table) {
+        return Ok((metadata_location, None));
+    }
+
+    // delta tables are opened via their _delta_log, which knows the files of the snapshot
+    if let Some(delta_location) = lookup_delta_location(table) {
+        return Ok((delta_location, None));
+    }
 
     let sd = table.storage_descriptor().ok_or_else(|| {
         DataFusionError::Execution(format!(
             "Could not find storage descriptor for {}.{} in glue",
-            database_name, table_name
+            table.database_name().unwrap_or_default(),
+            table.name()
         ))
     })?;
 
     let location = lookup_storage_location(sd)?;
     let format_arc = lookup_file_format(table.clone(), sd)?;
-    Ok((location, format_arc))
+    Ok((location, Some(format_arc)))
+}
+
+/// The schema of the columns of the glue table, None when the table has no columns
+fn lookup_glue_schema(table: &Table) -> Result<Option<SchemaRef>> {
+    match table.storage_descriptor() {
+        Some(sd) if !sd.columns().is_empty() => Ok(Some(Arc::new(glue_schema(sd.columns())?))),
+        _ => Ok(None),
+    }
+}
+
+fn lookup_iceberg_metadata_location(table: &Table) -> Option<String> {
+    let table_parameters = table.parameters()?;
+    let is_iceberg = table_parameters
+        .get("table_type")
+        .is_some_and(|table_type| table_type.eq_ignore_ascii_case("ICEBERG"));
+    if is_iceberg {
+        table_parameters.get("metadata_location").cloned()
+    } else {
+        None
+    }
+}
+
+/// The location of glue tables with spark.sql.sources.provider=delta (registered by spark) or table_type=DELTA
+fn lookup_delta_location(table: &Table) -> Option<String> {
+    let table_parameters = table.parameters()?;
+    let is_delta = table_parameters
+        .get("spark.sql.sources.provider")
+        .is_some_and(|provider| provider.eq_ignore_ascii_case("delta"))
+        || table_parameters
+            .get("table_type")
+            .is_some_and(|table_type| table_type.eq_ignore_ascii_case("DELTA"));
+    if !is_delta {
+        return None;
+    }
+
+    // spark stores the location in the path serde parameter, sd.location can be a placeholder
+    let sd = table.storage_descriptor()?;
+    let location = sd
+        .serde_info()
+        .and_then(|serde_info| serde_info.parameters())
+        .and_then(|parameters| parameters.get("path"))
+        .map(String::as_str)
+        .or(sd.location())?;
+    Some(replace_hadoop_s3_scheme(location))
+}
+
+/// Hadoop uses s3a:// (and s3n://) for s3:// locations
+fn replace_hadoop_s3_scheme(location: &str) -> String {
+    match location
+        .strip_prefix("s3a://")
+        .or_else(|| location.strip_prefix("s3n://"))
+    {
+        Some(path) => format!("s3://{path}"),
+        None => location.to_string(),
+    }
+}
+
+#[test]
+fn test_replace_hadoop_s3_scheme() {
+    assert_eq!(
+        "s3://bucket/db/tbl",
+        replace_hadoop_s3_scheme("s3a://bucket/db/tbl")
+    );
+    assert_eq!(
+        "s3://bucket/db/tbl",
+        replace_hadoop_s3_scheme("s3n://bucket/db/tbl")
+    );
+    assert_eq!(
+        "s3://bucket/db/tbl",
+        replace_hadoop_s3_scheme("s3://bucket/db/tbl")
+    );
 }
 
 fn lookup_storage_location(sd: &StorageDescriptor) -> Result<String> {
@@ -288,20 +778,14 @@ fn lookup_file_format(table: Table, sd: &StorageDescriptor) -> Result<Arc<dyn Fi
     };
 
     let table_parameters = table.parameters.unwrap_or_default();
-    let _table_type = table_parameters
-        .get("table_type")
-        .map(|x| x.as_str())
-        .unwrap_or_default();
-
-    // this can be delta...
-    // or ICEBERG...
-
-    /*
-        Table format: Apache Iceberg
-    Input format: -
-    Output format: -
-    Serde serialization lib:-
-         */
+    // eg: gzip for tables created by a crawler on .csv.gz files
+    let compression = match table_parameters
+        .get("compressionType")
+        .or_else(|| sd_parameters.get("compressionType"))
+    {
+        Some(compression_type) => parse_compression_type(compression_type)?,
+        None => FileCompressionType::UNCOMPRESSED,
+    };
 
     let item: (&str, &str, &str) = (input_format, output_format, serialization_library);
     let format_result: Result<Arc<dyn FileFormat>> = match item {
@@ -337,8 +821,14 @@ fn lookup_file_format(table: Table, sd: &StorageDescriptor) -> Result<Arc<dyn Fi
                 .unwrap_or(&empty_str)
                 .eq("1");
             format = format.with_has_header(has_header);
+            format = format.with_file_compression_type(compression);
             Ok(Arc::new(format))
         }
+        (
+            "org.apache.hadoop.hive.ql.io.orc.OrcInputFormat",
+            "org.apache.hadoop.hive.ql.io.orc.OrcOutputFormat",
+            "org.apache.hadoop.hive.ql.io.orc.OrcSerde",
+        ) => Ok(Arc::new(OrcFormat)),
         (
             "org.apache.hadoop.hive.ql.io.avro.AvroContainerInputFormat",
             "org.apache.hadoop.hive.ql.io.avro.AvroContainerOutputFormat",
@@ -348,17 +838,23 @@ fn lookup_file_format(table: Table, sd: &StorageDescriptor) -> Result<Arc<dyn Fi
             "org.apache.hadoop.mapred.TextInputFormat",
             "org.apache.hadoop.hive.ql.io.HiveIgnoreKeyTextOutputFormat",
             "org.apache.hive.hcatalog.data.JsonSerDe",
-        ) => Ok(Arc::new(JsonFormat::default())),
+        ) => Ok(Arc::new(
+            JsonFormat::default().with_file_compression_type(compression),
+        )),
         (
             "org.apache.hadoop.mapred.TextInputFormat",
             "org.apache.hadoop.hive.ql.io.HiveIgnoreKeyTextOutputFormat",
             "org.openx.data.jsonserde.JsonSerDe",
-        ) => Ok(Arc::new(JsonFormat::default())),
+        ) => Ok(Arc::new(
+            JsonFormat::default().with_file_compression_type(compression),
+        )),
         (
             "org.apache.hadoop.mapred.TextInputFormat",
             "org.apache.hadoop.hive.ql.io.HiveIgnoreKeyTextOutputFormat",
             "com.amazon.ionhiveserde.IonHiveSerDe",
-        ) => Ok(Arc::new(JsonFormat::default())),
+        ) => Ok(Arc::new(
+            JsonFormat::default().with_file_compression_type(compression),
+        )),
         _ => Err(DataFusionError::Execution(format!(
             "No support for: {}, {}, {:?} yet.",
             input_format, output_format, sd
@@ -394,21 +890,44 @@ fn parse_as_url(path: &str) -> Option<Url> {
 
 async fn build_listing_table(
     data_path: &str,
-    file_format: Option<Arc<dyn FileFormat>>,
+    options: Option<ListingOptions>,
+    schema: Option<SchemaRef>,
+    partition_cols: &[(String, DataType)],
     ctx: &SessionContext,
 ) -> Result<Arc<dyn TableProvider>> {
     let table_path = ListingTableUrl::parse(data_path)?;
-    let mut config = ListingTableConfig::new(table_path);
+    let mut config = ListingTableConfig::new(table_path.clone());
 
-    config = if let Some(format) = file_format {
-        config.with_listing_options(ListingOptions::new(format))
-    } else {
-        config.infer_options(&ctx.state()).await?
+    config = match options {
+        Some(options) => config.with_listing_options(options),
+        None => config.infer_options(&ctx.state()).await?,
+    };
+
+    config = match schema {
+        Some(schema) => config.with_schema(schema),
+        None => config.infer_schema(&ctx.state()).await?,
     };
 
-    config = config.infer_schema(&ctx.state()).await?;
+    // inferred partition columns which are also stored in the files are read from the files
+    let partition_cols = if partition_cols.is_empty() {
+        let file_schema = config.file_schema.clone();
+        infer_partition_cols(ctx, &table_path)
+            .await?
+            .into_iter()
+            .filter(|(name, _)| {
+                file_schema
+                    .as_ref()
+                    .is_none_or(|schema| schema.field_with_name(name).is_err())
+            })
+            .collect()
+    } else {
+        partition_cols.to_vec()
+    };
+    if let Some(options) = config.options.take() {
+        config = config.with_listing_options(options.with_table_partition_cols(partition_cols));
+    }
     let table = ListingTable::try_new(config)?;
-    Ok(Arc::new(table))
+    Ok(with_stripe_pruning(table))
 }
 
 async fn build_s3(url: &Url, sdk_config: &SdkConfig) -> Result<AmazonS3> {
@@ -460,3 +979,57 @@ async fn build_gcs(gcs_url: &Url) -> Result<GoogleCloudStorage> {
 
     Ok(gcs)
 }
+
+/// The account is taken from abfss://<container>@<account>.dfs.core.windows.net urls or from AZURE_STORAGE_ACCOUNT_NAME,
+/// credentials are read from the environment: AZURE_STORAGE_ACCOUNT_KEY, AZURE_STORAGE_SAS_KEY or
+/// AZURE_CLIENT_ID, AZURE_CLIENT_SECRET and AZURE_TENANT_ID.
+/// Set AZURE_STORAGE_USE_EMULATOR=true to use Azurite.
+fn build_azure(azure_url: &Url) -> Result<MicrosoftAzure> {
+    let azure = MicrosoftAzureBuilder::from_env()
+        .with_url(azure_url.as_str())
+        .build()?;
+    Ok(azure)
+}
+
+/// Files are fetched with (range) requests relative to the scheme, host and port of the url
+fn build_http(http_url: &Url, args: &Args) -> Result<HttpStore> {
+    let mut headers = HeaderMap::new();
+    for header in &args.http_header {
+        let (name, value) = parse_http_header(header)?;
+        headers.insert(name, value);
+    }
+    if let Some(bearer_token) = &args.http_bearer_token {
+        let value = HeaderValue::from_str(&format!("Bearer {bearer_token}"))
+            .map_err(|e| DataFusionError::Execution(format!("Invalid bearer token: {e}")))?;
+        headers.insert(AUTHORIZATION, value);
+    }
+
+    let client_options = ClientOptions::new()
+        .with_allow_http(http_url.scheme() == "http")
+        .with_default_headers(headers);
+    let http = HttpBuilder::new()
+        .with_url(&http_url[..url::Position::BeforePath])
+        .with_client_options(client_options)
+        .build()?;
+    Ok(http)
+}
+
+fn parse_http_header(s: &str) -> Result<(HeaderName, HeaderValue)> {
+    let (name, value) = s.split_once(':').ok_or_else(|| {
+        DataFusionError::Execution(format!("Invalid header {s}, expected name: value"))
+    })?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|e| DataFusionError::Execution(format!("Invalid header name in {s}: {e}")))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|e| DataFusionError::Execution(format!("Invalid header value in {s}: {e}")))?;
+    Ok((name, value))
+}
+
+#[test]
+fn test_parse_http_header() -> Result<()> {
+    let (name, value) = parse_http_header("X-Api-Key: secret")?;
+    assert_eq!("x-api-key", name.as_str());
+    assert_eq!("secret", value.to_str().unwrap());
+    assert!(parse_http_header("X-Api-Key").is_err());
+    Ok(())
+}
@@ -0,0 +1,436 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use datafusion::arrow::array::{ArrayRef, BooleanArray, UInt64Array};
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::catalog::Session;
+use datafusion::common::{Column, DFSchema, DataFusionError, GetExt, Result, ScalarValue};
+use datafusion::datasource::file_format::{FileFormat, FileFormatFactory};
+use datafusion::datasource::listing::{FileRange, ListingTable, PartitionedFile};
+use datafusion::datasource::physical_plan::{FileGroup, FileScanConfig, FileScanConfigBuilder};
+use datafusion::datasource::source::DataSourceExec;
+use datafusion::datasource::TableProvider;
+use datafusion::logical_expr::utils::conjunction;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown, TableType};
+use datafusion::physical_optimizer::pruning::{PruningPredicate, PruningStatistics};
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion_orc::OrcFormat;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use orc_rust::reader::metadata::{read_metadata_async, FileMetadata};
+use orc_rust::reader::AsyncChunkReader;
+use orc_rust::statistics::TypeStatistics;
+
+/// Makes datafusion recognize files with the .orc extension
+#[derive(Debug, Default)]
+pub struct OrcFormatFactory;
+
+impl GetExt for OrcFormatFactory {
+    fn get_ext(&self) -> String {
+        "orc".to_string()
+    }
+}
+
+impl FileFormatFactory for OrcFormatFactory {
+    fn create(
+        &self,
+        _state: &dyn Session,
+        _format_options: &HashMap<String, String>,
+    ) -> Result<Arc<dyn FileFormat>> {
+        Ok(Arc::new(OrcFormat))
+    }
+
+    fn default(&self) -> Arc<dyn FileFormat> {
+        Arc::new(OrcFormat)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Wraps listing tables of orc files in an OrcTable, other tables are returned as they are
+pub fn with_stripe_pruning(table: ListingTable) -> Arc<dyn TableProvider> {
+    if table.options().format.get_ext() == "orc" {
+        Arc::new(OrcTable { inner: table })
+    } else {
+        Arc::new(table)
+    }
+}
+
+/// A listing table of orc files which only reads the stripes of which the statistics (min, max and
+/// null count per column) can match the filters of the query
+#[derive(Debug)]
+pub struct OrcTable {
+    inner: ListingTable,
+}
+
+#[async_trait]
+impl TableProvider for OrcTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.inner.table_type()
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        self.inner.supports_filters_pushdown(filters)
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = self.inner.scan(state, projection, filters, limit).await?;
+        let Some(predicate) = conjunction(filters.to_vec()) else {
+            return Ok(plan);
+        };
+        let Some(config) = plan
+            .as_any()
+            .downcast_ref::<DataSourceExec>()
+            .and_then(|exec| exec.data_source().as_any().downcast_ref::<FileScanConfig>())
+        else {
+            return Ok(plan);
+        };
+
+        let schema = self.inner.schema();
+        let df_schema = DFSchema::try_from(schema.as_ref().clone())?;
+        let predicate = state.create_physical_expr(predicate, &df_schema)?;
+        let pruning_predicate = PruningPredicate::try_new(predicate, schema.clone())?;
+        if pruning_predicate.always_true() {
+            return Ok(plan);
+        }
+
+        let store = state.runtime_env().object_store(&config.object_store_url)?;
+        let mut file_groups = vec![];
+        for file_group in &config.file_groups {
+            let mut files = vec![];
+            for file in file_group.iter() {
+                files.extend(prune_stripes(&store, file, &schema, &pruning_predicate).await?);
+            }
+            file_groups.push(FileGroup::new(files));
+        }
+        let config = FileScanConfigBuilder::from(config.clone())
+            .with_file_groups(file_groups)
+            .build();
+        Ok(DataSourceExec::from_data_source(config))
+    }
+}
+
+/// Returns the file restricted to the byte ranges of the stripes which can match the predicate,
+/// no files when none of the stripes can match
+async fn prune_stripes(
+    store: &Arc<dyn ObjectStore>,
+    file: &PartitionedFile,
+    schema: &Schema,
+    pruning_predicate: &PruningPredicate,
+) -> Result<Vec<PartitionedFile>> {
+    // files which were already split in ranges are read as they are
+    if file.range.is_some() {
+        return Ok(vec![file.clone()]);
+    }
+
+    let mut reader = ObjectStoreChunkReader {
+        store: store.clone(),
+        location: file.object_meta.location.clone(),
+        size: file.object_meta.size,
+    };
+    let metadata = read_metadata_async(&mut reader)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    let stripes = get_stripe_statistics(&metadata);
+    let statistics = StripePruningStatistics {
+        schema,
+        stripes: &stripes,
+    };
+    let matches = pruning_predicate.prune(&statistics)?;
+    if matches.iter().all(|matches| *matches) {
+        return Ok(vec![file.clone()]);
+    }
+
+    Ok(get_stripe_ranges(&stripes, &matches)
+        .into_iter()
+        .map(|range| PartitionedFile {
+            range: Some(FileRange {
+                start: range.start as i64,
+                end: range.end as i64,
+            }),
+            ..file.clone()
+        })
+        .collect())
+}
+
+/// The statistics of a stripe, per top level column
+#[derive(Debug, Default)]
+struct StripeStatistics {
+    offset: u64,
+    length: u64,
+    row_count: u64,
+    columns: HashMap<String, ColumnStatistics>,
+}
+
+#[derive(Debug, Default)]
+struct ColumnStatistics {
+    min: Option<ScalarValue>,
+    max: Option<ScalarValue>,
+    null_count: Option<u64>,
+}
+
+fn get_stripe_statistics(metadata: &FileMetadata) -> Vec<StripeStatistics> {
+    let columns = metadata
+        .root_data_type()
+        .children()
+        .iter()
+        .map(|column| (column.name().to_string(), column.data_type().column_index()))
+        .collect::<Vec<_>>();
+    metadata
+        .stripe_metadatas()
+        .iter()
+        .map(|stripe| StripeStatistics {
+            offset: stripe.offset(),
+            length: stripe.index_length() + stripe.data_length() + stripe.footer_length(),
+            row_count: stripe.number_of_rows(),
+            columns: columns
+                .iter()
+                .filter_map(|(name, column_index)| {
+                    let statistics = stripe.column_statistics().get(*column_index)?;
+                    let (min, max) = match statistics.type_statistics() {
+                        Some(TypeStatistics::Integer { min, max, .. }) => (
+                            ScalarValue::Int64(Some(*min)),
+                            ScalarValue::Int64(Some(*max)),
+                        ),
+                        Some(TypeStatistics::Double { min, max, .. }) => (
+                            ScalarValue::Float64(Some(*min)),
+                            ScalarValue::Float64(Some(*max)),
+                        ),
+                        Some(TypeStatistics::String { min, max, .. }) => (
+                            ScalarValue::Utf8(Some(min.clone())),
+                            ScalarValue::Utf8(Some(max.clone())),
+                        ),
+                        Some(TypeStatistics::Date { min, max }) => (
+                            ScalarValue::Date32(Some(*min)),
+                            ScalarValue::Date32(Some(*max)),
+                        ),
+                        _ => (ScalarValue::Null, ScalarValue::Null),
+                    };
+                    // the number of values excludes the nulls
+                    let null_count = stripe
+                        .number_of_rows()
+                        .checked_sub(statistics.number_of_values());
+                    Some((
+                        name.clone(),
+                        ColumnStatistics {
+                            min: (!min.is_null()).then_some(min),
+                            max: (!max.is_null()).then_some(max),
+                            null_count,
+                        },
+                    ))
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// The byte ranges of the consecutive stripes which match.
+/// The orc reader reads the stripes of which the offset is in the range.
+fn get_stripe_ranges(stripes: &[StripeStatistics], matches: &[bool]) -> Vec<Range<u64>> {
+    let mut ranges: Vec<Range<u64>> = vec![];
+    let mut previous_matches = false;
+    for (stripe, matches) in stripes.iter().zip(matches) {
+        if *matches {
+            let end = stripe.offset + stripe.length;
+            match ranges.last_mut() {
+                Some(range) if previous_matches => range.end = end,
+                _ => ranges.push(stripe.offset..end),
+            }
+        }
+        previous_matches = *matches;
+    }
+    ranges
+}
+
+#[test]
+fn test_get_stripe_ranges() {
+    let stripes = [(3, 100), (103, 100), (203, 100), (303, 50)]
+        .into_iter()
+        .map(|(offset, length)| StripeStatistics {
+            offset,
+            length,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![3..203, 303..353],
+        get_stripe_ranges(&stripes, &[true, true, false, true])
+    );
+    assert_eq!(
+        vec![103..203],
+        get_stripe_ranges(&stripes, &[false, true, false, false])
+    );
+    assert!(get_stripe_ranges(&stripes, &[false; 4]).is_empty());
+}
+
+struct StripePruningStatistics<'a> {
+    schema: &'a Schema,
+    stripes: &'a [StripeStatistics],
+}
+
+impl StripePruningStatistics<'_> {
+    /// The values (cast to the type of the column) per stripe, null when unknown
+    fn values(
+        &self,
+        column: &Column,
+        value: impl Fn(&ColumnStatistics) -> Option<&ScalarValue>,
+    ) -> Option<ArrayRef> {
+        let data_type = self.schema.field_with_name(&column.name).ok()?.data_type();
+        let null = ScalarValue::try_from(data_type).ok()?;
+        let values = self.stripes.iter().map(|stripe| {
+            stripe
+                .columns
+                .get(&column.name)
+                .and_then(&value)
+                .and_then(|value| value.cast_to(data_type).ok())
+                .unwrap_or_else(|| null.clone())
+        });
+        ScalarValue::iter_to_array(values).ok()
+    }
+}
+
+impl PruningStatistics for StripePruningStatistics<'_> {
+    fn min_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.values(column, |statistics| statistics.min.as_ref())
+    }
+
+    fn max_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.values(column, |statistics| statistics.max.as_ref())
+    }
+
+    fn num_containers(&self) -> usize {
+        self.stripes.len()
+    }
+
+    fn null_counts(&self, column: &Column) -> Option<ArrayRef> {
+        let null_counts = self.stripes.iter().map(|stripe| {
+            stripe
+                .columns
+                .get(&column.name)
+                .and_then(|statistics| statistics.null_count)
+        });
+        Some(Arc::new(UInt64Array::from_iter(null_counts)))
+    }
+
+    fn row_counts(&self, _column: &Column) -> Option<ArrayRef> {
+        let row_counts = self.stripes.iter().map(|stripe| stripe.row_count);
+        Some(Arc::new(UInt64Array::from_iter_values(row_counts)))
+    }
+
+    fn contained(&self, _column: &Column, _values: &HashSet<ScalarValue>) -> Option<BooleanArray> {
+        None
+    }
+}
+
+#[test]
+fn test_prune_stripes_with_statistics() {
+    use datafusion::arrow::datatypes::{DataType, Field};
+    use datafusion::logical_expr::{col, lit};
+    use datafusion::prelude::SessionContext;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, true),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    let stripe = |min_id: i64, max_id: i64, null_names: u64| StripeStatistics {
+        row_count: 100,
+        columns: HashMap::from([
+            (
+                "id".to_string(),
+                ColumnStatistics {
+                    min: Some(ScalarValue::Int64(Some(min_id))),
+                    max: Some(ScalarValue::Int64(Some(max_id))),
+                    null_count: Some(0),
+                },
+            ),
+            (
+                "name".to_string(),
+                ColumnStatistics {
+                    null_count: Some(null_names),
+                    ..Default::default()
+                },
+            ),
+        ]),
+        ..Default::default()
+    };
+    let stripes = vec![
+        stripe(1, 100, 0),
+        stripe(101, 200, 100),
+        stripe(201, 300, 5),
+    ];
+    let statistics = StripePruningStatistics {
+        schema: &schema,
+        stripes: &stripes,
+    };
+
+    let ctx = SessionContext::new();
+    let df_schema = DFSchema::try_from(schema.as_ref().clone()).unwrap();
+    let prune = |expr: Expr| {
+        let predicate = ctx.create_physical_expr(expr, &df_schema).unwrap();
+        PruningPredicate::try_new(predicate, schema.clone())
+            .unwrap()
+            .prune(&statistics)
+            .unwrap()
+    };
+    assert_eq!(vec![false, true, false], prune(col("id").eq(lit(150))));
+    assert_eq!(vec![false, true, true], prune(col("id").gt(lit(100))));
+    assert_eq!(vec![true, false, true], prune(col("name").is_not_null()));
+    // without min and max only the stripe in which all names are null is pruned
+    assert_eq!(vec![true, false, true], prune(col("name").eq(lit("foo"))));
+}
+
+/// Reads the (metadata) byte ranges of orc files from the object store
+struct ObjectStoreChunkReader {
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+    size: u64,
+}
+
+impl AsyncChunkReader for ObjectStoreChunkReader {
+    fn len(&mut self) -> BoxFuture<'_, std::io::Result<u64>> {
+        let size = self.size;
+        async move { Ok(size) }.boxed()
+    }
+
+    fn get_bytes(
+        &mut self,
+        offset_from_start: u64,
+        length: u64,
+    ) -> BoxFuture<'_, std::io::Result<Bytes>> {
+        async move {
+            self.store
+                .get_range(
+                    &self.location,
+                    offset_from_start..offset_from_start + length,
+                )
+                .await
+                .map_err(std::io::Error::other)
+        }
+        .boxed()
+    }
+}
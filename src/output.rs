@@ -0,0 +1,140 @@
+use std::str::FromStr;
+
+use datafusion::arrow::ipc::writer::FileWriter;
+use datafusion::common::cast::as_uint64_array;
+use datafusion::common::config::{CsvOptions, JsonOptions, TableParquetOptions};
+use datafusion::common::parsers::CompressionTypeVariant;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::dataframe::DataFrameWriteOptions;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::prelude::*;
+use futures::TryStreamExt;
+use object_store::PutPayload;
+
+use crate::args::OutputFormat;
+
+/// Writes the result of the provided dataframe to a single file at path.
+/// Returns the number of rows which were written.
+pub async fn write_output(
+    df: DataFrame,
+    path: &str,
+    format: Option<OutputFormat>,
+    compression: Option<&str>,
+) -> Result<u64> {
+    let format = format
+        .or_else(|| detect_output_format(path))
+        .ok_or_else(|| {
+            DataFusionError::Execution(format!(
+                "Could not derive output format from {path}, please provide --output-format"
+            ))
+        })?;
+
+    let write_options = DataFrameWriteOptions::new().with_single_file_output(true);
+    let batches = match format {
+        OutputFormat::Parquet => {
+            let mut parquet_options = TableParquetOptions::default();
+            if let Some(compression) = compression {
+                parquet_options.global.compression = Some(compression.to_string());
+            }
+            df.write_parquet(path, write_options, Some(parquet_options))
+                .await?
+        }
+        OutputFormat::Csv => {
+            let csv_options = CsvOptions::default()
+                .with_has_header(true)
+                .with_compression(parse_compression(compression)?);
+            df.write_csv(path, write_options, Some(csv_options)).await?
+        }
+        OutputFormat::Ndjson => {
+            let json_options = JsonOptions {
+                compression: parse_compression(compression)?,
+                ..Default::default()
+            };
+            df.write_json(path, write_options, Some(json_options))
+                .await?
+        }
+        OutputFormat::Arrow => {
+            if compression.is_some() {
+                return Err(DataFusionError::Execution(
+                    "Compression is not supported for arrow output".to_string(),
+                ));
+            }
+            return write_arrow_file(df, path).await;
+        }
+    };
+
+    let mut rows = 0;
+    for batch in batches {
+        rows += as_uint64_array(batch.column(0))?
+            .iter()
+            .flatten()
+            .sum::<u64>();
+    }
+    Ok(rows)
+}
+
+/// DataFrame has no write_arrow and a COPY TO writes a directory when the path has no extension,
+/// so the arrow ipc file is written with the arrow FileWriter and put in the object store as a single file
+async fn write_arrow_file(df: DataFrame, path: &str) -> Result<u64> {
+    let url = ListingTableUrl::parse(path)?;
+    let store = df
+        .task_ctx()
+        .runtime_env()
+        .object_store(url.object_store())?;
+    let schema = df.schema().inner().clone();
+    let mut stream = df.execute_stream().await?;
+
+    let mut rows = 0;
+    let mut writer = FileWriter::try_new(vec![], &schema)?;
+    while let Some(batch) = stream.try_next().await? {
+        rows += batch.num_rows() as u64;
+        writer.write(&batch)?;
+    }
+    let bytes = writer.into_inner()?;
+    store.put(url.prefix(), PutPayload::from(bytes)).await?;
+    Ok(rows)
+}
+
+fn detect_output_format(path: &str) -> Option<OutputFormat> {
+    let extension = std::path::Path::new(path)
+        .extension()?
+        .to_str()?
+        .to_lowercase();
+    match extension.as_str() {
+        "parquet" => Some(OutputFormat::Parquet),
+        "csv" => Some(OutputFormat::Csv),
+        "json" | "ndjson" | "jsonl" => Some(OutputFormat::Ndjson),
+        "arrow" | "ipc" | "feather" => Some(OutputFormat::Arrow),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_detect_output_format() {
+    assert_eq!(
+        Some(OutputFormat::Parquet),
+        detect_output_format("s3://bucket/result.parquet")
+    );
+    assert_eq!(
+        Some(OutputFormat::Csv),
+        detect_output_format("/tmp/result.CSV")
+    );
+    assert_eq!(
+        Some(OutputFormat::Ndjson),
+        detect_output_format("result.jsonl")
+    );
+    assert_eq!(
+        Some(OutputFormat::Arrow),
+        detect_output_format("gs://bucket/result.feather")
+    );
+    assert_eq!(None, detect_output_format("/tmp/result"));
+}
+
+fn parse_compression(compression: Option<&str>) -> Result<CompressionTypeVariant> {
+    match compression {
+        Some(compression) => CompressionTypeVariant::from_str(compression).map_err(|e| {
+            DataFusionError::Execution(format!("Unsupported compression {compression}: {e}"))
+        }),
+        None => Ok(CompressionTypeVariant::UNCOMPRESSED),
+    }
+}
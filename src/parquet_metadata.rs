@@ -0,0 +1,197 @@
+use std::fmt::Display;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, BooleanArray, Int64Array, StringArray};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::parquet::arrow::arrow_reader::ArrowReaderOptions;
+use datafusion::parquet::arrow::async_reader::{AsyncFileReader, ParquetObjectReader};
+use datafusion::parquet::file::metadata::ColumnChunkMetaData;
+use datafusion::parquet::file::statistics::Statistics;
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+use object_store::ObjectMeta;
+
+/// A column chunk of a row group in a parquet file
+struct ColumnChunkReport {
+    file: String,
+    row_group: i64,
+    row_group_num_rows: i64,
+    column: String,
+    physical_type: String,
+    compression: String,
+    encodings: String,
+    num_values: i64,
+    compressed_size: i64,
+    uncompressed_size: i64,
+    null_count: Option<i64>,
+    min: Option<String>,
+    max: Option<String>,
+    has_column_index: bool,
+    has_offset_index: bool,
+    has_bloom_filter: bool,
+}
+
+/// Builds a table with a row per column chunk per row group of the parquet files found at data_path
+pub async fn build_parquet_metadata_table(
+    ctx: &SessionContext,
+    data_path: &str,
+) -> Result<Arc<dyn TableProvider>> {
+    let table_url = ListingTableUrl::parse(data_path)?;
+    let store = ctx.runtime_env().object_store(table_url.object_store())?;
+    let state = ctx.state();
+    let objects: Vec<ObjectMeta> = table_url
+        .list_all_files(&state, store.as_ref(), "")
+        .await?
+        .try_collect()
+        .await?;
+
+    let mut reports = vec![];
+    for object in objects
+        .iter()
+        .filter(|object| !is_hidden(&table_url, object))
+    {
+        let file = format!("{}{}", table_url.object_store().as_str(), object.location);
+        let mut reader = ParquetObjectReader::new(store.clone(), object.location.clone())
+            .with_file_size(object.size);
+        let options = ArrowReaderOptions::new().with_page_index(true);
+        let metadata = reader.get_metadata(Some(&options)).await?;
+
+        for (row_group_index, row_group) in metadata.row_groups().iter().enumerate() {
+            for column in row_group.columns() {
+                reports.push(build_column_chunk_report(
+                    &file,
+                    row_group_index as i64,
+                    row_group.num_rows(),
+                    column,
+                ));
+            }
+        }
+    }
+
+    let batch = to_record_batch(reports)?;
+    let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+    Ok(Arc::new(table))
+}
+
+/// Files in folders (or with names) starting with _ or . are not part of the data (eg: _SUCCESS, _delta_log)
+fn is_hidden(table_url: &ListingTableUrl, object: &ObjectMeta) -> bool {
+    object
+        .location
+        .prefix_match(table_url.prefix())
+        .is_some_and(|mut parts| parts.any(|part| part.as_ref().starts_with(['_', '.'])))
+}
+
+fn build_column_chunk_report(
+    file: &str,
+    row_group: i64,
+    row_group_num_rows: i64,
+    column: &ColumnChunkMetaData,
+) -> ColumnChunkReport {
+    let statistics = column.statistics();
+    let (min, max) = statistics.map(format_min_max).unwrap_or_default();
+    ColumnChunkReport {
+        file: file.to_string(),
+        row_group,
+        row_group_num_rows,
+        column: column.column_path().string(),
+        physical_type: column.column_type().to_string(),
+        compression: column.compression().to_string(),
+        encodings: column
+            .encodings()
+            .iter()
+            .map(|encoding| encoding.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        num_values: column.num_values(),
+        compressed_size: column.compressed_size(),
+        uncompressed_size: column.uncompressed_size(),
+        null_count: statistics
+            .and_then(|statistics| statistics.null_count_opt())
+            .map(|null_count| null_count as i64),
+        min,
+        max,
+        has_column_index: column.column_index_offset().is_some(),
+        has_offset_index: column.offset_index_offset().is_some(),
+        has_bloom_filter: column.bloom_filter_offset().is_some(),
+    }
+}
+
+fn format_min_max(statistics: &Statistics) -> (Option<String>, Option<String>) {
+    fn display<T: Display>(min: Option<&T>, max: Option<&T>) -> (Option<String>, Option<String>) {
+        (min.map(T::to_string), max.map(T::to_string))
+    }
+
+    match statistics {
+        Statistics::Boolean(statistics) => display(statistics.min_opt(), statistics.max_opt()),
+        Statistics::Int32(statistics) => display(statistics.min_opt(), statistics.max_opt()),
+        Statistics::Int64(statistics) => display(statistics.min_opt(), statistics.max_opt()),
+        Statistics::Float(statistics) => display(statistics.min_opt(), statistics.max_opt()),
+        Statistics::Double(statistics) => display(statistics.min_opt(), statistics.max_opt()),
+        Statistics::Int96(statistics) => (
+            statistics.min_opt().map(|min| format!("{min:?}")),
+            statistics.max_opt().map(|max| format!("{max:?}")),
+        ),
+        // binary values which are not valid utf8 are shown as hex
+        Statistics::ByteArray(statistics) => (
+            statistics.min_opt().map(|min| format_bytes(min.data())),
+            statistics.max_opt().map(|max| format_bytes(max.data())),
+        ),
+        Statistics::FixedLenByteArray(statistics) => (
+            statistics.min_opt().map(|min| format_bytes(min.data())),
+            statistics.max_opt().map(|max| format_bytes(max.data())),
+        ),
+    }
+}
+
+fn format_bytes(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => bytes.iter().map(|byte| format!("{byte:02x}")).collect(),
+    }
+}
+
+#[test]
+fn test_format_bytes() {
+    assert_eq!("abc", format_bytes(b"abc"));
+    assert_eq!("00ff", format_bytes(&[0x00, 0xff]));
+}
+
+fn to_record_batch(reports: Vec<ColumnChunkReport>) -> Result<RecordBatch> {
+    let strings = |f: fn(&ColumnChunkReport) -> Option<String>| -> ArrayRef {
+        Arc::new(StringArray::from(reports.iter().map(f).collect::<Vec<_>>()))
+    };
+    let integers = |f: fn(&ColumnChunkReport) -> Option<i64>| -> ArrayRef {
+        Arc::new(Int64Array::from(reports.iter().map(f).collect::<Vec<_>>()))
+    };
+    let booleans = |f: fn(&ColumnChunkReport) -> bool| -> ArrayRef {
+        Arc::new(BooleanArray::from(
+            reports.iter().map(f).collect::<Vec<_>>(),
+        ))
+    };
+
+    let batch = RecordBatch::try_from_iter(vec![
+        ("file", strings(|r| Some(r.file.clone()))),
+        ("row_group", integers(|r| Some(r.row_group))),
+        (
+            "row_group_num_rows",
+            integers(|r| Some(r.row_group_num_rows)),
+        ),
+        ("column", strings(|r| Some(r.column.clone()))),
+        ("physical_type", strings(|r| Some(r.physical_type.clone()))),
+        ("compression", strings(|r| Some(r.compression.clone()))),
+        ("encodings", strings(|r| Some(r.encodings.clone()))),
+        ("num_values", integers(|r| Some(r.num_values))),
+        ("compressed_size", integers(|r| Some(r.compressed_size))),
+        ("uncompressed_size", integers(|r| Some(r.uncompressed_size))),
+        ("null_count", integers(|r| r.null_count)),
+        ("min", strings(|r| r.min.clone())),
+        ("max", strings(|r| r.max.clone())),
+        ("has_column_index", booleans(|r| r.has_column_index)),
+        ("has_offset_index", booleans(|r| r.has_offset_index)),
+        ("has_bloom_filter", booleans(|r| r.has_bloom_filter)),
+    ])?;
+    Ok(batch)
+}
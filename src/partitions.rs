@@ -0,0 +1,112 @@
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::Result;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+
+/// Number of files which are sampled to infer the partition columns
+const PARTITION_SAMPLE_FILES: usize = 1000;
+
+/// Infers the hive style partition columns (eg: dt=2024-01-01/region=eu/) from the directory layout
+/// of the (first 1000) files at the table path. The columns are typed as Utf8, use --partition-cols
+/// for other types. No partition columns are inferred when the files have a mixed layout.
+pub async fn infer_partition_cols(
+    ctx: &SessionContext,
+    table_path: &ListingTableUrl,
+) -> Result<Vec<(String, DataType)>> {
+    let store = ctx.runtime_env().object_store(table_path.object_store())?;
+    let state = ctx.state();
+    let mut objects = table_path
+        .list_all_files(&state, store.as_ref(), "")
+        .await?;
+
+    let mut files = vec![];
+    while files.len() < PARTITION_SAMPLE_FILES {
+        let Some(object) = objects.try_next().await? else {
+            break;
+        };
+        let Some(parts) = object.location.prefix_match(table_path.prefix()) else {
+            continue;
+        };
+        let parts = parts
+            .map(|part| part.as_ref().to_string())
+            .collect::<Vec<_>>();
+        // files in folders (or with names) starting with _ or . are not part of the data (eg: _SUCCESS)
+        if !parts.iter().any(|part| part.starts_with(['_', '.'])) {
+            files.push(parts);
+        }
+    }
+    Ok(infer_partition_cols_from_files(&files))
+}
+
+/// Infers the partition columns from the (relative) paths of the files, split in parts
+fn infer_partition_cols_from_files(files: &[Vec<String>]) -> Vec<(String, DataType)> {
+    let mut names: Option<Vec<&str>> = None;
+    for parts in files {
+        let partition_names = parse_partitions(&parts[..parts.len().saturating_sub(1)])
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        match &names {
+            None => names = Some(partition_names),
+            Some(names) if *names != partition_names => return vec![],
+            Some(_) => {}
+        }
+    }
+
+    names
+        .unwrap_or_default()
+        .into_iter()
+        .map(|name| (name.to_string(), DataType::Utf8))
+        .collect()
+}
+
+#[test]
+fn test_infer_partition_cols_from_files() {
+    let files = |paths: &[&str]| {
+        paths
+            .iter()
+            .map(|path| path.split('/').map(String::from).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        vec![
+            ("dt".to_string(), DataType::Utf8),
+            ("region".to_string(), DataType::Utf8)
+        ],
+        infer_partition_cols_from_files(&files(&[
+            "dt=2024-01-01/region=eu/data.csv",
+            "dt=2024-01-02/region=us/data.csv"
+        ]))
+    );
+    assert!(infer_partition_cols_from_files(&files(&[
+        "dt=2024-01-01/data.csv",
+        "dt=2024-01-02/region=us/data.csv"
+    ]))
+    .is_empty());
+    assert!(infer_partition_cols_from_files(&files(&["data.csv"])).is_empty());
+    assert!(infer_partition_cols_from_files(&[]).is_empty());
+}
+
+/// The leading name=value directories of the path (relative to the table path)
+fn parse_partitions(directories: &[String]) -> Vec<(&str, &str)> {
+    directories
+        .iter()
+        .map_while(|directory| directory.split_once('='))
+        .collect()
+}
+
+#[test]
+fn test_parse_partitions() {
+    let directories = vec![
+        "dt=2024-01-01".to_string(),
+        "region=eu".to_string(),
+        "other".to_string(),
+        "x=1".to_string(),
+    ];
+    assert_eq!(
+        vec![("dt", "2024-01-01"), ("region", "eu")],
+        parse_partitions(&directories)
+    );
+    assert!(parse_partitions(&["data".to_string()]).is_empty());
+}
@@ -0,0 +1,164 @@
+use std::io::Write;
+
+use datafusion::arrow::csv::WriterBuilder;
+use datafusion::arrow::json::{ArrayWriter, LineDelimitedWriter};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+use datafusion::arrow::util::pretty::pretty_format_batches;
+use datafusion::common::Result;
+use datafusion::prelude::DataFrame;
+
+use crate::args::PrintFormat;
+
+/// Executes the dataframe and prints at most limit rows on stdout in the requested format
+pub async fn show_dataframe(
+    df: DataFrame,
+    limit: Option<usize>,
+    format: PrintFormat,
+    header: bool,
+) -> Result<()> {
+    let df = match limit {
+        Some(limit) => df.limit(0, Some(limit))?,
+        None => df,
+    };
+    let batches = df.collect().await?;
+    print_batches(&batches, format, header)
+}
+
+/// Prints the batches on stdout in the requested format
+pub fn print_batches(batches: &[RecordBatch], format: PrintFormat, header: bool) -> Result<()> {
+    let stdout = std::io::stdout();
+    let mut writer = stdout.lock();
+    write_batches(&mut writer, batches, format, header)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_batches<W: Write>(
+    writer: &mut W,
+    batches: &[RecordBatch],
+    format: PrintFormat,
+    header: bool,
+) -> Result<()> {
+    match format {
+        PrintFormat::Table => {
+            writeln!(writer, "{}", pretty_format_batches(batches)?)?;
+        }
+        PrintFormat::Json => {
+            let mut json_writer = ArrayWriter::new(&mut *writer);
+            json_writer.write_batches(&batches.iter().collect::<Vec<_>>())?;
+            json_writer.finish()?;
+            writeln!(writer)?;
+        }
+        PrintFormat::Ndjson => {
+            let mut json_writer = LineDelimitedWriter::new(&mut *writer);
+            json_writer.write_batches(&batches.iter().collect::<Vec<_>>())?;
+            json_writer.finish()?;
+        }
+        PrintFormat::Csv | PrintFormat::Tsv => {
+            let delimiter = if format == PrintFormat::Tsv {
+                b'\t'
+            } else {
+                b','
+            };
+            let mut csv_writer = WriterBuilder::new()
+                .with_header(header)
+                .with_delimiter(delimiter)
+                .build(&mut *writer);
+            for batch in batches {
+                csv_writer.write(batch)?;
+            }
+        }
+        PrintFormat::Markdown => write_markdown(writer, batches, header)?,
+    }
+    Ok(())
+}
+
+fn write_markdown<W: Write>(writer: &mut W, batches: &[RecordBatch], header: bool) -> Result<()> {
+    let Some(schema) = batches.first().map(|batch| batch.schema()) else {
+        return Ok(());
+    };
+
+    if header {
+        let names = schema
+            .fields()
+            .iter()
+            .map(|field| escape_markdown(field.name()))
+            .collect::<Vec<_>>();
+        writeln!(writer, "| {} |", names.join(" | "))?;
+        writeln!(writer, "|{}", " --- |".repeat(names.len()))?;
+    }
+
+    let options = FormatOptions::default().with_display_error(true);
+    for batch in batches {
+        let formatters = batch
+            .columns()
+            .iter()
+            .map(|column| ArrayFormatter::try_new(column.as_ref(), &options))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for row in 0..batch.num_rows() {
+            let cells = formatters
+                .iter()
+                .map(|formatter| escape_markdown(&formatter.value(row).to_string()))
+                .collect::<Vec<_>>();
+            writeln!(writer, "| {} |", cells.join(" | "))?;
+        }
+    }
+    Ok(())
+}
+
+fn escape_markdown(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+fn build_test_batch() -> RecordBatch {
+    use datafusion::arrow::array::{Int32Array, StringArray};
+    use std::sync::Arc;
+
+    RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int32Array::from(vec![1, 2])) as _),
+        ("name", Arc::new(StringArray::from(vec!["a|b", "c"])) as _),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_write_batches_as_markdown() -> Result<()> {
+    let mut buffer = Vec::new();
+    write_batches(
+        &mut buffer,
+        &[build_test_batch()],
+        PrintFormat::Markdown,
+        true,
+    )?;
+    assert_eq!(
+        "| id | name |\n| --- | --- |\n| 1 | a\\|b |\n| 2 | c |\n",
+        String::from_utf8(buffer).unwrap()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_write_batches_as_tsv_without_header() -> Result<()> {
+    let mut buffer = Vec::new();
+    write_batches(&mut buffer, &[build_test_batch()], PrintFormat::Tsv, false)?;
+    assert_eq!("1\ta|b\n2\tc\n", String::from_utf8(buffer).unwrap());
+    Ok(())
+}
+
+#[test]
+fn test_write_batches_as_ndjson() -> Result<()> {
+    let mut buffer = Vec::new();
+    write_batches(
+        &mut buffer,
+        &[build_test_batch()],
+        PrintFormat::Ndjson,
+        true,
+    )?;
+    assert_eq!(
+        "{\"id\":1,\"name\":\"a|b\"}\n{\"id\":2,\"name\":\"c\"}\n",
+        String::from_utf8(buffer).unwrap()
+    );
+    Ok(())
+}
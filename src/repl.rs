@@ -0,0 +1,204 @@
+use std::env;
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::prelude::SessionContext;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use crate::args::{schema_query, PrintFormat};
+use crate::print::show_dataframe;
+
+const HELP: &str = "\
+Statements end with ; and may span multiple lines.
+
+.help               Show this help
+.tables             List the registered tables
+.schema [table]     Show the schema of a table (default: tbl)
+.limit [rows]       Show or change the number of rows to show
+.format [format]    Show or change the output format (table, json, ndjson, csv, tsv, markdown)
+.quit               Exit qv";
+
+struct Settings {
+    limit: usize,
+    format: PrintFormat,
+    header: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum DotCommand {
+    Help,
+    Quit,
+    Tables,
+    Schema(String),
+    Limit(Option<usize>),
+    Format(Option<PrintFormat>),
+}
+
+/// Reads statements and dot-commands from the terminal and runs them against ctx until .quit or EOF
+pub async fn run(
+    ctx: &SessionContext,
+    limit: usize,
+    format: PrintFormat,
+    header: bool,
+) -> Result<()> {
+    let mut settings = Settings {
+        limit,
+        format,
+        header,
+    };
+
+    let mut editor = DefaultEditor::new().map_err(readline_error)?;
+    let history_path = get_history_path();
+    if let Some(history_path) = &history_path {
+        // there is no history the first time qv runs
+        let _ = editor.load_history(history_path);
+    }
+
+    println!("Type .tables to list the registered tables, .help for help and .quit to exit.");
+
+    let mut statement = String::new();
+    loop {
+        let prompt = if statement.is_empty() { "qv> " } else { "  > " };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                let trimmed = line.trim();
+                if statement.is_empty() && trimmed.starts_with('.') {
+                    editor.add_history_entry(trimmed).map_err(readline_error)?;
+                    match parse_dot_command(trimmed) {
+                        Ok(DotCommand::Quit) => break,
+                        Ok(command) => {
+                            if let Err(e) = run_dot_command(ctx, command, &mut settings).await {
+                                eprintln!("{e}");
+                            }
+                        }
+                        Err(e) => eprintln!("{e}"),
+                    }
+                    continue;
+                }
+
+                if trimmed.is_empty() {
+                    continue;
+                }
+                statement.push_str(&line);
+                statement.push('\n');
+
+                if trimmed.ends_with(';') {
+                    editor
+                        .add_history_entry(statement.trim())
+                        .map_err(readline_error)?;
+                    if let Err(e) = run_statement(ctx, &statement, &settings).await {
+                        eprintln!("{e}");
+                    }
+                    statement.clear();
+                }
+            }
+            Err(ReadlineError::Interrupted) => statement.clear(),
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(readline_error(e)),
+        }
+    }
+
+    if let Some(history_path) = &history_path {
+        editor.save_history(history_path).map_err(readline_error)?;
+    }
+    Ok(())
+}
+
+async fn run_statement(ctx: &SessionContext, statement: &str, settings: &Settings) -> Result<()> {
+    let df = ctx.sql(statement).await?;
+    show_dataframe(df, Some(settings.limit), settings.format, settings.header).await
+}
+
+async fn run_dot_command(
+    ctx: &SessionContext,
+    command: DotCommand,
+    settings: &mut Settings,
+) -> Result<()> {
+    match command {
+        DotCommand::Help => println!("{HELP}"),
+        DotCommand::Quit => {}
+        DotCommand::Tables => {
+            let df = ctx.sql("SHOW TABLES").await?;
+            show_dataframe(df, None, settings.format, settings.header).await?;
+        }
+        DotCommand::Schema(table_name) => {
+            let df = ctx.sql(&schema_query(&[table_name])).await?;
+            show_dataframe(df, None, settings.format, settings.header).await?;
+        }
+        DotCommand::Limit(Some(limit)) => settings.limit = limit,
+        DotCommand::Limit(None) => println!("{}", settings.limit),
+        DotCommand::Format(Some(format)) => settings.format = format,
+        DotCommand::Format(None) => println!("{:?}", settings.format),
+    }
+    Ok(())
+}
+
+fn parse_dot_command(line: &str) -> Result<DotCommand> {
+    let mut parts = line.split_whitespace();
+    let command = parts.next().unwrap_or_default();
+    let argument = parts.next();
+    match command {
+        ".help" => Ok(DotCommand::Help),
+        ".quit" | ".exit" => Ok(DotCommand::Quit),
+        ".tables" => Ok(DotCommand::Tables),
+        ".schema" => Ok(DotCommand::Schema(argument.unwrap_or("tbl").to_string())),
+        ".limit" => argument
+            .map(|limit| {
+                limit
+                    .parse::<usize>()
+                    .map_err(|e| DataFusionError::Execution(format!("Invalid limit {limit}: {e}")))
+            })
+            .transpose()
+            .map(DotCommand::Limit),
+        ".format" => argument
+            .map(|format| {
+                PrintFormat::from_str(format, true).map_err(|e| {
+                    DataFusionError::Execution(format!("Invalid format {format}: {e}"))
+                })
+            })
+            .transpose()
+            .map(DotCommand::Format),
+        _ => Err(DataFusionError::Execution(format!(
+            "Unknown command {command}, type .help for help"
+        ))),
+    }
+}
+
+#[test]
+fn test_parse_dot_command() {
+    assert_eq!(DotCommand::Quit, parse_dot_command(".exit").unwrap());
+    assert_eq!(
+        DotCommand::Schema("tbl".to_string()),
+        parse_dot_command(".schema").unwrap()
+    );
+    assert_eq!(
+        DotCommand::Schema("other".to_string()),
+        parse_dot_command(".schema other").unwrap()
+    );
+    assert_eq!(
+        DotCommand::Limit(Some(25)),
+        parse_dot_command(".limit 25").unwrap()
+    );
+    assert_eq!(
+        DotCommand::Limit(None),
+        parse_dot_command(".limit").unwrap()
+    );
+    assert_eq!(
+        DotCommand::Format(Some(PrintFormat::Csv)),
+        parse_dot_command(".format CSV").unwrap()
+    );
+    assert!(parse_dot_command(".limit many").is_err());
+    assert!(parse_dot_command(".unknown").is_err());
+}
+
+fn get_history_path() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".qv_history"))
+}
+
+fn readline_error(e: ReadlineError) -> DataFusionError {
+    DataFusionError::External(Box::new(e))
+}
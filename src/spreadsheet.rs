@@ -0,0 +1,542 @@
+use std::any::Any;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use calamine::{open_workbook_auto_from_rs, Data, DataType as _, Range, Reader};
+use datafusion::arrow::array::{
+    ArrayRef, BooleanBuilder, Float64Builder, Int64Builder, StringBuilder,
+    TimestampMillisecondBuilder,
+};
+use datafusion::arrow::compute::concat_batches;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::catalog::Session;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::logical_expr::{Expr, TableType};
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+use object_store::ObjectMeta;
+
+use crate::args::Args;
+use crate::input_format::find_first_data_file;
+
+const SPREADSHEET_EXTENSIONS: [&str; 5] = ["xlsx", "xlsm", "xlsb", "xls", "ods"];
+
+/// The type of the values in a column, inferred from its non empty cells
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CellType {
+    Int,
+    Float,
+    Bool,
+    DateTime,
+    String,
+}
+
+/// Returns true when the path is a spreadsheet file (xlsx, xlsm, xlsb, xls or ods)
+pub fn is_spreadsheet(data_path: &str) -> bool {
+    let file_name = data_path.rsplit('/').next().unwrap_or_default();
+    file_name.rsplit_once('.').is_some_and(|(_, extension)| {
+        SPREADSHEET_EXTENSIONS
+            .iter()
+            .any(|spreadsheet_extension| extension.eq_ignore_ascii_case(spreadsheet_extension))
+    })
+}
+
+#[test]
+fn test_is_spreadsheet() {
+    assert!(is_spreadsheet("file:///data/report.xlsx"));
+    assert!(is_spreadsheet("s3://bucket/budget.ODS"));
+    assert!(!is_spreadsheet("s3://bucket/report.xlsx/data.csv"));
+    assert!(!is_spreadsheet("file:///data/"));
+}
+
+/// Spreadsheets are zip (xlsx, xlsm, xlsb and ods) or compound (xls) files
+pub fn is_spreadsheet_data(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1")
+}
+
+/// Builds a table with a sheet of the spreadsheets at the path (a workbook or a folder with workbooks).
+/// With an explicit input format (force) the files do not need a spreadsheet extension.
+/// Returns None when the path does not contain spreadsheets.
+pub async fn open_spreadsheet_table(
+    ctx: &SessionContext,
+    data_path: &str,
+    force: bool,
+    args: &Args,
+) -> Result<Option<Arc<dyn TableProvider>>> {
+    let Some(first_file) = find_first_data_file(ctx, data_path).await? else {
+        return Ok(None);
+    };
+    if !force && !is_spreadsheet(first_file.location.as_ref()) {
+        return Ok(None);
+    }
+
+    let table_path = ListingTableUrl::parse(data_path)?;
+    let store = ctx.runtime_env().object_store(table_path.object_store())?;
+    let state = ctx.state();
+    let objects: Vec<ObjectMeta> = table_path
+        .list_all_files(&state, store.as_ref(), "")
+        .await?
+        .try_collect()
+        .await?;
+    let mut workbooks = vec![];
+    for object in objects {
+        let file_name = object.location.filename().unwrap_or_default();
+        if file_name.starts_with(['_', '.']) || !(force || is_spreadsheet(file_name)) {
+            continue;
+        }
+        let bytes = store.get(&object.location).await?.bytes().await?;
+        workbooks.push((file_name.to_string(), bytes.to_vec()));
+    }
+    build_spreadsheet_table(workbooks, args).map(Some)
+}
+
+/// Builds a table with a sheet (--sheet, the first sheet by default) of the workbooks (name, data).
+/// The column names are taken from the header row of the first workbook and the type of every
+/// column is inferred from its cells in all workbooks.
+/// With --schema the sheets of the workbooks are collected as well.
+pub fn build_spreadsheet_table(
+    workbooks: Vec<(String, Vec<u8>)>,
+    args: &Args,
+) -> Result<Arc<dyn TableProvider>> {
+    let mut sheets = vec![];
+    let mut ranges = vec![];
+    for (name, bytes) in workbooks {
+        let mut workbook = open_workbook_auto_from_rs(Cursor::new(bytes))
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let sheet_names = workbook.sheet_names();
+        if args.schema {
+            for (i, sheet_name) in sheet_names.iter().enumerate() {
+                let range = workbook
+                    .worksheet_range(sheet_name)
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?;
+                let used_cells = match (range.start(), range.end()) {
+                    (Some(start), Some(end)) => {
+                        Some(format!("{}:{}", cell_reference(start), cell_reference(end)))
+                    }
+                    _ => None,
+                };
+                sheets.push((name.clone(), i + 1, sheet_name.clone(), used_cells));
+            }
+        }
+
+        let sheet_name = find_sheet_name(&sheet_names, args.sheet.as_deref())?;
+        let range = workbook
+            .worksheet_range(&sheet_name)
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        ranges.push(match args.sheet_range {
+            Some(cell_range) => range.range(cell_range.start, cell_range.end),
+            None => range,
+        });
+    }
+
+    let batch = build_record_batch(&ranges, args.sheet_header_row)?;
+    let sheets = if args.schema {
+        Some(build_sheets_batch(sheets)?)
+    } else {
+        None
+    };
+    Ok(Arc::new(SpreadsheetTable {
+        inner: MemTable::try_new(batch.schema(), vec![vec![batch]])?,
+        sheets,
+    }))
+}
+
+/// A sheet of the workbooks, which also holds the sheets of the workbooks when they were collected
+#[derive(Debug)]
+pub struct SpreadsheetTable {
+    inner: MemTable,
+    sheets: Option<RecordBatch>,
+}
+
+impl SpreadsheetTable {
+    /// The workbook, index, name and used cells of the sheets (only with --schema)
+    pub fn sheets(&self) -> Option<&RecordBatch> {
+        self.sheets.as_ref()
+    }
+}
+
+/// Returns the sheets of the workbooks when all the tables are spreadsheets of which the sheets were
+/// collected (see --schema), None otherwise
+pub async fn get_sheets(
+    ctx: &SessionContext,
+    table_names: &[String],
+) -> Result<Option<RecordBatch>> {
+    let mut batches = vec![];
+    for table_name in table_names {
+        let table = ctx.table_provider(table_name.as_str()).await?;
+        match table
+            .as_any()
+            .downcast_ref::<SpreadsheetTable>()
+            .and_then(SpreadsheetTable::sheets)
+        {
+            Some(sheets) => batches.push(sheets.clone()),
+            None => return Ok(None),
+        }
+    }
+    let Some(first) = batches.first() else {
+        return Ok(None);
+    };
+    Ok(Some(concat_batches(&first.schema(), &batches)?))
+}
+
+#[async_trait]
+impl TableProvider for SpreadsheetTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.inner.table_type()
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.inner.scan(state, projection, filters, limit).await
+    }
+}
+
+/// A row per sheet with the workbook, the index (starting at 1) and name of the sheet and its used cells
+fn build_sheets_batch(sheets: Vec<(String, usize, String, Option<String>)>) -> Result<RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("workbook", DataType::Utf8, false),
+        Field::new("sheet_index", DataType::Int64, false),
+        Field::new("sheet_name", DataType::Utf8, false),
+        Field::new("used_cells", DataType::Utf8, true),
+    ]));
+    let mut workbooks = StringBuilder::new();
+    let mut indexes = Int64Builder::with_capacity(sheets.len());
+    let mut names = StringBuilder::new();
+    let mut used_cells = StringBuilder::new();
+    for (workbook, index, name, cells) in sheets {
+        workbooks.append_value(workbook);
+        indexes.append_value(index as i64);
+        names.append_value(name);
+        used_cells.append_option(cells);
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(workbooks.finish()),
+        Arc::new(indexes.finish()),
+        Arc::new(names.finish()),
+        Arc::new(used_cells.finish()),
+    ];
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// Finds the sheet by name or by index (starting at 1), the first sheet when none is provided
+fn find_sheet_name(sheet_names: &[String], sheet: Option<&str>) -> Result<String> {
+    let found = match sheet {
+        None => sheet_names.first(),
+        Some(sheet) => sheet_names
+            .iter()
+            .find(|sheet_name| *sheet_name == sheet)
+            .or_else(|| {
+                sheet
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| index.checked_sub(1))
+                    .and_then(|index| sheet_names.get(index))
+            }),
+    };
+    found.cloned().ok_or_else(|| {
+        DataFusionError::Execution(format!(
+            "Could not find sheet {}, available sheets are: {}",
+            sheet.unwrap_or_default(),
+            sheet_names.join(", ")
+        ))
+    })
+}
+
+#[test]
+fn test_find_sheet_name() {
+    let sheet_names = vec!["Summary".to_string(), "2024".to_string()];
+    assert_eq!("Summary", find_sheet_name(&sheet_names, None).unwrap());
+    assert_eq!("2024", find_sheet_name(&sheet_names, Some("2024")).unwrap());
+    assert_eq!("2024", find_sheet_name(&sheet_names, Some("2")).unwrap());
+    assert_eq!("Summary", find_sheet_name(&sheet_names, Some("1")).unwrap());
+    assert!(find_sheet_name(&sheet_names, Some("0")).is_err());
+    assert!(find_sheet_name(&sheet_names, Some("Details")).is_err());
+    assert!(find_sheet_name(&[], None).is_err());
+}
+
+/// Formats a zero based (row, column) position as cell reference, eg: AB12
+fn cell_reference((row, column): (u32, u32)) -> String {
+    let mut letters = vec![];
+    let mut column = column + 1;
+    while column > 0 {
+        let remainder = (column - 1) % 26;
+        letters.push(char::from(b'A' + remainder as u8));
+        column = (column - 1) / 26;
+    }
+    letters.iter().rev().collect::<String>() + &(row + 1).to_string()
+}
+
+#[test]
+fn test_cell_reference() {
+    assert_eq!("A1", cell_reference((0, 0)));
+    assert_eq!("Z10", cell_reference((9, 25)));
+    assert_eq!("AB3", cell_reference((2, 27)));
+}
+
+/// Builds a record batch with the rows below the header row (starting at 1, 0 for no header) of
+/// the ranges (the same sheet of several workbooks), the column names are taken from the first range.
+/// Rows without values are skipped.
+fn build_record_batch(ranges: &[Range<Data>], header_row: usize) -> Result<RecordBatch> {
+    let width = ranges.iter().map(Range::width).max().unwrap_or_default();
+    let mut header = None;
+    let mut rows = vec![];
+    for range in ranges {
+        let mut range_rows = range.rows();
+        if header_row > 0 {
+            let range_header = range_rows.nth(header_row - 1).ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "Header row {header_row} is beyond the last row of the sheet"
+                ))
+            })?;
+            header.get_or_insert(range_header);
+        }
+        rows.extend(range_rows.filter(|row| row.iter().any(|cell| cell_type(cell).is_some())));
+    }
+
+    let names = column_names(header, width);
+    let mut fields = Vec::with_capacity(width);
+    let mut columns = Vec::with_capacity(width);
+    for (i, name) in names.into_iter().enumerate() {
+        let cells = rows
+            .iter()
+            .map(|row| row.get(i).unwrap_or(&Data::Empty))
+            .collect::<Vec<_>>();
+        let (data_type, column) = build_column(&cells);
+        fields.push(Field::new(name, data_type, true));
+        columns.push(column);
+    }
+    let schema = Arc::new(Schema::new(fields));
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// The column names from the header, column_<n> for empty (or missing) header cells
+/// and <name>_<n> for names which were already taken
+fn column_names(header: Option<&[Data]>, width: usize) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(width);
+    for i in 0..width {
+        let name = header
+            .and_then(|header| header.get(i))
+            .and_then(cell_to_string)
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("column_{}", i + 1));
+        let name = if names.contains(&name) {
+            format!("{name}_{}", i + 1)
+        } else {
+            name
+        };
+        names.push(name);
+    }
+    names
+}
+
+#[test]
+fn test_column_names() {
+    let header = vec![
+        Data::String("id".to_string()),
+        Data::Empty,
+        Data::String(" amount ".to_string()),
+        Data::String("id".to_string()),
+        Data::Float(2024.0),
+    ];
+    assert_eq!(
+        vec!["id", "column_2", "amount", "id_4", "2024", "column_6"],
+        column_names(Some(header.as_slice()), 6)
+    );
+    assert_eq!(vec!["column_1", "column_2"], column_names(None, 2));
+}
+
+fn cell_type(cell: &Data) -> Option<CellType> {
+    match cell {
+        Data::Empty | Data::Error(_) => None,
+        Data::Int(_) => Some(CellType::Int),
+        // numbers are stored as floats in xlsx files
+        Data::Float(value) if value.fract() == 0.0 && value.abs() < 9.0e15 => Some(CellType::Int),
+        Data::Float(_) => Some(CellType::Float),
+        Data::Bool(_) => Some(CellType::Bool),
+        Data::DateTime(value) if value.is_datetime() => Some(CellType::DateTime),
+        Data::DateTimeIso(_) if cell.as_datetime().is_some() => Some(CellType::DateTime),
+        _ => Some(CellType::String),
+    }
+}
+
+/// Columns with ints and floats are typed as floats, columns with mixed other types as strings
+fn infer_column_type(cells: &[&Data]) -> DataType {
+    let cell_type = cells
+        .iter()
+        .filter_map(|cell| cell_type(cell))
+        .try_fold(None, |column_type, cell_type| {
+            match (column_type, cell_type) {
+                (None, cell_type) => Some(Some(cell_type)),
+                (Some(column_type), cell_type) if column_type == cell_type => Some(Some(cell_type)),
+                (Some(CellType::Int), CellType::Float) | (Some(CellType::Float), CellType::Int) => {
+                    Some(Some(CellType::Float))
+                }
+                _ => None,
+            }
+        })
+        .flatten();
+    match cell_type {
+        Some(CellType::Int) => DataType::Int64,
+        Some(CellType::Float) => DataType::Float64,
+        Some(CellType::Bool) => DataType::Boolean,
+        Some(CellType::DateTime) => DataType::Timestamp(TimeUnit::Millisecond, None),
+        Some(CellType::String) | None => DataType::Utf8,
+    }
+}
+
+#[test]
+fn test_infer_column_type() {
+    let int = Data::Float(1.0);
+    let float = Data::Float(1.5);
+    let string = Data::String("a".to_string());
+    let iso = Data::DateTimeIso("2024-01-01T12:00:00".to_string());
+    assert_eq!(DataType::Int64, infer_column_type(&[&int, &Data::Empty]));
+    assert_eq!(DataType::Float64, infer_column_type(&[&int, &float]));
+    assert_eq!(
+        DataType::Boolean,
+        infer_column_type(&[&Data::Bool(true), &Data::Error(calamine::CellErrorType::NA)])
+    );
+    assert_eq!(
+        DataType::Timestamp(TimeUnit::Millisecond, None),
+        infer_column_type(&[&iso])
+    );
+    assert_eq!(DataType::Utf8, infer_column_type(&[&int, &string]));
+    assert_eq!(DataType::Utf8, infer_column_type(&[&Data::Empty]));
+    assert_eq!(DataType::Utf8, infer_column_type(&[]));
+}
+
+fn build_column(cells: &[&Data]) -> (DataType, ArrayRef) {
+    let data_type = infer_column_type(cells);
+    let column: ArrayRef = match data_type {
+        DataType::Int64 => {
+            let mut builder = Int64Builder::with_capacity(cells.len());
+            for cell in cells {
+                builder.append_option(match cell {
+                    Data::Int(value) => Some(*value),
+                    Data::Float(value) => Some(*value as i64),
+                    _ => None,
+                });
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Float64 => {
+            let mut builder = Float64Builder::with_capacity(cells.len());
+            for cell in cells {
+                builder.append_option(match cell {
+                    Data::Int(value) => Some(*value as f64),
+                    Data::Float(value) => Some(*value),
+                    _ => None,
+                });
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Boolean => {
+            let mut builder = BooleanBuilder::with_capacity(cells.len());
+            for cell in cells {
+                builder.append_option(cell.get_bool());
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Timestamp(_, _) => {
+            let mut builder = TimestampMillisecondBuilder::with_capacity(cells.len());
+            for cell in cells {
+                builder.append_option(
+                    cell.as_datetime()
+                        .map(|value| value.and_utc().timestamp_millis()),
+                );
+            }
+            Arc::new(builder.finish())
+        }
+        _ => {
+            let mut builder = StringBuilder::new();
+            for cell in cells {
+                builder.append_option(cell_to_string(cell));
+            }
+            Arc::new(builder.finish())
+        }
+    };
+    (data_type, column)
+}
+
+fn cell_to_string(cell: &Data) -> Option<String> {
+    match cell {
+        Data::Empty | Data::Error(_) => None,
+        Data::String(value) => Some(value.clone()),
+        Data::DateTime(value) if value.is_datetime() => {
+            cell.as_datetime().map(|value| value.to_string())
+        }
+        other => Some(other.to_string()),
+    }
+}
+
+#[test]
+fn test_build_record_batch() -> Result<()> {
+    let mut range = Range::new((0, 0), (3, 1));
+    range.set_value((0, 0), Data::String("name".to_string()));
+    range.set_value((0, 1), Data::String("amount".to_string()));
+    range.set_value((1, 0), Data::String("a".to_string()));
+    range.set_value((1, 1), Data::Float(1.0));
+    range.set_value((3, 0), Data::String("b".to_string()));
+    range.set_value((3, 1), Data::Float(2.5));
+
+    let ranges = vec![range];
+    let batch = build_record_batch(&ranges, 1)?;
+    assert_eq!(2, batch.num_rows());
+    assert_eq!("name", batch.schema().field(0).name());
+    assert_eq!(&DataType::Utf8, batch.schema().field(0).data_type());
+    assert_eq!(&DataType::Float64, batch.schema().field(1).data_type());
+
+    let batch = build_record_batch(&ranges, 0)?;
+    assert_eq!(3, batch.num_rows());
+    assert_eq!("column_1", batch.schema().field(0).name());
+    assert_eq!(&DataType::Utf8, batch.schema().field(1).data_type());
+
+    assert!(build_record_batch(&ranges, 5).is_err());
+
+    // the rows of all workbooks, typed as string when the cells of the workbooks have other types
+    let mut other = Range::new((0, 0), (1, 1));
+    other.set_value((0, 0), Data::String("name".to_string()));
+    other.set_value((0, 1), Data::String("total".to_string()));
+    other.set_value((1, 0), Data::String("c".to_string()));
+    other.set_value((1, 1), Data::String("n/a".to_string()));
+    let batch = build_record_batch(&[ranges[0].clone(), other], 1)?;
+    assert_eq!(3, batch.num_rows());
+    assert_eq!("amount", batch.schema().field(1).name());
+    assert_eq!(&DataType::Utf8, batch.schema().field(1).data_type());
+    Ok(())
+}
+
+#[test]
+fn test_build_sheets_batch() -> Result<()> {
+    let batch = build_sheets_batch(vec![
+        (
+            "a.xlsx".to_string(),
+            1,
+            "Summary".to_string(),
+            Some("A1:C10".to_string()),
+        ),
+        ("a.xlsx".to_string(), 2, "Empty".to_string(), None),
+    ])?;
+    assert_eq!(2, batch.num_rows());
+    assert_eq!(1, batch.column(3).null_count());
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::io::Read;
+use std::sync::Arc;
+
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use datafusion::datasource::listing::{
+    ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+};
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::prelude::SessionContext;
+use object_store::memory::InMemory;
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+use url::Url;
+
+use crate::args::{Args, InputFormat};
+use crate::arrow_ipc::{build_arrow_stream_table, is_arrow_stream};
+use crate::input_format::{
+    build_file_format, detect_compression, detect_input_format, is_json_array,
+};
+use crate::spreadsheet::build_spreadsheet_table;
+use crate::transcode::{json_array_to_ndjson, transcode};
+
+/// Reads all data from stdin and loads it in a memory table.
+/// Compressed data (gzip, zstd, bzip2 or xz) is decompressed and transcoded (see --csv-encoding) first.
+/// The data is parsed by the regular file formats via an in-memory object store.
+pub async fn build_stdin_table(
+    ctx: &SessionContext,
+    args: &Args,
+) -> Result<Arc<dyn TableProvider>> {
+    let mut bytes = vec![];
+    std::io::stdin().read_to_end(&mut bytes)?;
+    if bytes.is_empty() {
+        return Err(DataFusionError::Execution(
+            "Could not find any data on stdin".to_string(),
+        ));
+    }
+    let compression = detect_compression(&bytes);
+    if compression.is_compressed() {
+        let mut decompressed = vec![];
+        compression
+            .convert_read(std::io::Cursor::new(bytes))?
+            .read_to_end(&mut decompressed)?;
+        bytes = decompressed;
+    }
+    if args.needs_transcoding() {
+        bytes = transcode(bytes, args)?;
+    }
+    let input_format = args
+        .get_input_format()
+        .unwrap_or_else(|| detect_input_format(&bytes));
+    // json arrays are converted to newline delimited json, also without --json-array
+    if input_format == InputFormat::Json && !args.json_array && is_json_array(&bytes) {
+        bytes = json_array_to_ndjson(&bytes)?;
+    }
+    if input_format == InputFormat::Arrow && is_arrow_stream(&bytes) {
+        return build_arrow_stream_table(&bytes);
+    }
+    if input_format == InputFormat::Spreadsheet {
+        return build_spreadsheet_table(vec![("stdin".to_string(), bytes)], args);
+    }
+
+    let store = InMemory::new();
+    store
+        .put(&Path::from("stdin"), PutPayload::from(bytes))
+        .await?;
+    let store_url = Url::parse("memory://stdin/")
+        .map_err(|e| DataFusionError::Execution(format!("Failed to parse url, {e}")))?;
+    ctx.runtime_env()
+        .register_object_store(&store_url, Arc::new(store));
+
+    let table_path = ListingTableUrl::parse("memory://stdin/stdin")?;
+    let options = ListingOptions::new(build_file_format(
+        input_format,
+        FileCompressionType::UNCOMPRESSED,
+        args,
+    )?)
+    .with_file_extension("");
+    let config = ListingTableConfig::new(table_path)
+        .with_listing_options(options)
+        .infer_schema(&ctx.state())
+        .await?;
+    let table = Arc::new(ListingTable::try_new(config)?);
+
+    let state = ctx.state();
+    let table = MemTable::load(table, None, &state).await?;
+    Ok(Arc::new(table))
+}
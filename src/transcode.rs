@@ -0,0 +1,200 @@
+use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+use datafusion::datasource::TableProvider;
+use datafusion::prelude::SessionContext;
+use encoding_rs::UTF_8;
+use futures::TryStreamExt;
+use object_store::memory::InMemory;
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore, PutPayload};
+use url::Url;
+
+use crate::args::{Args, InputFormat};
+use crate::input_format::{build_file_format, compression_from_file_name};
+
+/// Each transcoded table gets its own in-memory object store
+static TRANSCODED_TABLES: AtomicUsize = AtomicUsize::new(0);
+
+/// Converts the data to UTF-8 (--csv-encoding), empties the csv null values (--csv-null-value) and
+/// converts json arrays to newline delimited json (--json-array)
+pub fn transcode(bytes: Vec<u8>, args: &Args) -> Result<Vec<u8>> {
+    let bytes = match args.csv_encoding {
+        Some(encoding) if encoding != UTF_8 => {
+            let (text, _, had_errors) = encoding.decode(&bytes);
+            if had_errors {
+                return Err(DataFusionError::Execution(format!(
+                    "Data is not valid {} encoded text",
+                    encoding.name()
+                )));
+            }
+            text.into_owned().into_bytes()
+        }
+        _ => bytes,
+    };
+    if let Some(null_value) = &args.csv_null_value {
+        replace_csv_null_values(&bytes, null_value, args)
+    } else if args.json_array {
+        json_array_to_ndjson(&bytes)
+    } else {
+        Ok(bytes)
+    }
+}
+
+/// Replaces the null values by empty values, which are read as null.
+/// Datafusion only applies the null regex of the csv format when it infers the schema.
+fn replace_csv_null_values(bytes: &[u8], null_value: &str, args: &Args) -> Result<Vec<u8>> {
+    let to_error = |e: csv::Error| DataFusionError::Execution(format!("Failed to read csv, {e}"));
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(!args.csv_no_header)
+        .flexible(true)
+        .delimiter(args.csv_delimiter.unwrap_or(b','))
+        .quote(args.csv_quote.unwrap_or(b'"'))
+        .escape(args.csv_escape)
+        .comment(args.csv_comment)
+        .from_reader(bytes);
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .delimiter(args.csv_delimiter.unwrap_or(b','))
+        .quote(args.csv_quote.unwrap_or(b'"'))
+        .double_quote(args.csv_escape.is_none())
+        .escape(args.csv_escape.unwrap_or(b'\\'))
+        .from_writer(vec![]);
+    if !args.csv_no_header {
+        writer
+            .write_byte_record(reader.byte_headers().map_err(to_error)?)
+            .map_err(to_error)?;
+    }
+    for record in reader.byte_records() {
+        let record = record.map_err(to_error)?;
+        let values = record.iter().map(|value| {
+            if value == null_value.as_bytes() {
+                &[][..]
+            } else {
+                value
+            }
+        });
+        writer.write_record(values).map_err(to_error)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| DataFusionError::Execution(format!("Failed to write csv, {e}")))
+}
+
+/// Converts a json array of values to newline delimited json
+pub fn json_array_to_ndjson(bytes: &[u8]) -> Result<Vec<u8>> {
+    let values: Vec<serde_json::Value> = serde_json::from_slice(bytes)
+        .map_err(|e| DataFusionError::Execution(format!("Data is not a json array, {e}")))?;
+    let mut ndjson = vec![];
+    for value in values {
+        serde_json::to_writer(&mut ndjson, &value)
+            .map_err(|e| DataFusionError::Execution(format!("Failed to write json, {e}")))?;
+        ndjson.push(b'\n');
+    }
+    Ok(ndjson)
+}
+
+#[test]
+fn test_transcode() {
+    use clap::Parser;
+
+    let args = Args::parse_from(["qv", "data.csv", "--csv-encoding", "windows-1252"]);
+    assert_eq!(
+        "name\ncaf\u{e9}\n".as_bytes(),
+        transcode(b"name\ncaf\xe9\n".to_vec(), &args).unwrap()
+    );
+
+    let args = Args::parse_from(["qv", "data.json", "--json-array"]);
+    assert_eq!(
+        b"{\"a\":1}\n{\"a\":2,\"b\":\"x\"}\n".to_vec(),
+        transcode(b" [{\"a\": 1}, {\"a\": 2, \"b\": \"x\"}]".to_vec(), &args).unwrap()
+    );
+    assert!(transcode(b"{\"a\": 1}".to_vec(), &args).is_err());
+
+    let args = Args::parse_from([
+        "qv",
+        "data.csv",
+        "--csv-null-value",
+        "NA",
+        "--csv-comment",
+        "#",
+    ]);
+    assert_eq!(
+        b"id,name\n1,\n2,\"b,NA\"\n".to_vec(),
+        transcode(b"# comment\nid,name\n1,NA\n2,\"b,NA\"\n".to_vec(), &args).unwrap()
+    );
+}
+
+/// Builds a table for data which has to be transcoded before it can be read.
+/// The (decompressed) files are transcoded into an in-memory object store, keeping their relative
+/// location so partition columns can still be inferred from the directory layout.
+pub async fn build_transcoded_table(
+    ctx: &SessionContext,
+    data_path: &str,
+    schema: Option<SchemaRef>,
+    args: &Args,
+) -> Result<Arc<dyn TableProvider>> {
+    let table_url = ListingTableUrl::parse(data_path)?;
+    let store = ctx.runtime_env().object_store(table_url.object_store())?;
+    let state = ctx.state();
+    let objects: Vec<ObjectMeta> = table_url
+        .list_all_files(&state, store.as_ref(), "")
+        .await?
+        .try_collect()
+        .await?;
+
+    let memory_store = InMemory::new();
+    for object in objects {
+        let file_name = object.location.filename().unwrap_or_default();
+        if file_name.starts_with(['_', '.']) {
+            continue;
+        }
+        let mut bytes = store.get(&object.location).await?.bytes().await?.to_vec();
+        let compression = compression_from_file_name(file_name);
+        if compression.is_compressed() {
+            let mut decompressed = vec![];
+            compression
+                .convert_read(std::io::Cursor::new(bytes))?
+                .read_to_end(&mut decompressed)?;
+            bytes = decompressed;
+        }
+        let relative_location = object
+            .location
+            .as_ref()
+            .strip_prefix(table_url.prefix().as_ref())
+            .map(|location| location.trim_start_matches('/'))
+            .filter(|location| !location.is_empty())
+            .unwrap_or(file_name);
+        memory_store
+            .put(
+                &Path::from(relative_location),
+                PutPayload::from(transcode(bytes, args)?),
+            )
+            .await?;
+    }
+
+    let store_url = format!(
+        "memory://transcoded{}/",
+        TRANSCODED_TABLES.fetch_add(1, Ordering::SeqCst)
+    );
+    let url = Url::parse(&store_url)
+        .map_err(|e| DataFusionError::Execution(format!("Failed to parse url, {e}")))?;
+    ctx.runtime_env()
+        .register_object_store(&url, Arc::new(memory_store));
+
+    let input_format = args.get_input_format().unwrap_or(InputFormat::Csv);
+    let file_format = build_file_format(input_format, FileCompressionType::UNCOMPRESSED, args)?;
+    crate::build_listing_table(
+        &store_url,
+        Some(ListingOptions::new(file_format).with_file_extension("")),
+        schema,
+        &args.partition_cols,
+        ctx,
+    )
+    .await
+}
@@ -29,6 +29,18 @@ async fn run_without_file_exits_with_usage() -> datafusion::common::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn run_with_glue_catalog_and_describe_without_path_exits_with_usage(
+) -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    cmd.arg("--glue-catalog")
+        .arg("--describe")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("<PATH>"));
+    Ok(())
+}
+
 #[tokio::test]
 async fn run_with_local_avro_file() -> datafusion::common::Result<()> {
     let mut cmd = get_qv_cmd()?;
@@ -183,3 +195,878 @@ async fn run_with_local_deltalake() -> datafusion::common::Result<()> {
         .stdout(data_predicate);
     Ok(())
 }
+
+#[tokio::test]
+async fn run_with_output_to_local_parquet_file() -> datafusion::common::Result<()> {
+    let output_path = env::temp_dir().join(format!("qv-output-{}.parquet", std::process::id()));
+    let output_path = output_path.to_str().unwrap();
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path("data/json/ndjson-sample.json"))
+        .arg("-q")
+        .arg("SELECT url from tbl")
+        .arg("--output")
+        .arg(output_path);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Wrote"));
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd.arg(output_path);
+
+    let header_predicate = build_row_regex_predicate(vec!["url"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["https://www.yelp.com/search"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+
+    std::fs::remove_file(output_path).ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_output_to_local_arrow_file_without_extension() -> datafusion::common::Result<()> {
+    let output_path = env::temp_dir().join(format!("qv-output-arrow-{}", std::process::id()));
+    let output_path = output_path.to_str().unwrap();
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path("data/json/ndjson-sample.json"))
+        .arg("-q")
+        .arg("SELECT url from tbl")
+        .arg("--output")
+        .arg(output_path)
+        .arg("--output-format")
+        .arg("arrow");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Wrote"));
+    assert!(std::path::Path::new(output_path).is_file());
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd.arg(output_path).arg("--input-format").arg("arrow");
+
+    let data_predicate = build_row_regex_predicate(vec!["https://www.yelp.com/search"]);
+
+    cmd.assert().success().stdout(data_predicate);
+
+    std::fs::remove_file(output_path).ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_csv_format() -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path("data/json/ndjson-sample.json"))
+        .arg("-q")
+        .arg("SELECT url from tbl")
+        .arg("--format")
+        .arg("csv");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("url\n"))
+        .stdout(predicate::str::contains("https://www.yelp.com/search"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_multiple_named_local_files() -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(format!(
+            "blogs={}",
+            get_qv_testing_path("data/parquet/generated_simple_numerics/blogs.parquet")
+        ))
+        .arg(format!(
+            "samples={}",
+            get_qv_testing_path("data/json/ndjson-sample.json")
+        ))
+        .arg("-q")
+        .arg("SELECT (SELECT count(*) FROM blogs) > 0 AS has_blogs, url FROM samples");
+
+    let header_predicate = build_row_regex_predicate(vec!["has_blogs", "url"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["true", "https://www.yelp.com/search"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_local_iceberg_table() -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path("data/iceberg/db/COVID-19_NYT"))
+        .arg("-q")
+        .arg("select * from tbl order by date, county, state, fips, cases, deaths");
+
+    let header_predicate =
+        build_row_regex_predicate(vec!["date", "county", "state", "fips", "case", "deaths"]);
+
+    let data_predicate = build_row_regex_predicate(vec![
+        "2020-01-21",
+        "Snohomish",
+        "Washington",
+        "53061",
+        "1",
+        "0",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_local_deltalake_at_unknown_version() -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path("data/delta/COVID-19_NYT"))
+        .arg("--table-version")
+        .arg("999999");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("available versions are 0 to"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_local_deltalake_history() -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path("data/delta/COVID-19_NYT"))
+        .arg("--history");
+
+    let header_predicate = build_row_regex_predicate(vec![
+        "version",
+        "timestamp",
+        "operation",
+        "operation_parameters",
+        "operation_metrics",
+        "engine_info",
+        "user_name",
+    ]);
+
+    cmd.assert().success().stdout(header_predicate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_local_parquet_file_metadata() -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path(
+            "data/parquet/generated_simple_numerics/blogs.parquet",
+        ))
+        .arg("--parquet-meta")
+        .arg("-q")
+        .arg("select row_group, column, compression from tbl where column = 'blog_id'");
+
+    let header_predicate = build_row_regex_predicate(vec!["row_group", "column", "compression"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["0", "blog_id"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_written_parquet_file_metadata() -> datafusion::common::Result<()> {
+    use datafusion::dataframe::DataFrameWriteOptions;
+
+    let file_path = env::temp_dir().join(format!("qv_parquet_meta_{}.parquet", std::process::id()));
+    let ctx = datafusion::prelude::SessionContext::new();
+    ctx.sql(
+        "SELECT * FROM (VALUES (CAST(3 AS BIGINT), 'b'), (CAST(7 AS BIGINT), 'a')) AS t(id, name)",
+    )
+    .await?
+    .write_parquet(
+        file_path.to_str().unwrap(),
+        DataFrameWriteOptions::new(),
+        None,
+    )
+    .await?;
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(file_path.to_str().unwrap())
+        .arg("--parquet-meta")
+        .arg("-q")
+        .arg("SELECT column, min, max FROM tbl ORDER BY column");
+
+    let header_predicate = build_row_regex_predicate(vec!["column", "min", "max"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(build_row_regex_predicate(vec!["id", "3", "7"]))
+        .stdout(build_row_regex_predicate(vec!["name", "a", "b"]));
+
+    std::fs::remove_file(&file_path).ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_describe() -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path("data/json/ndjson-sample.json"))
+        .arg("--describe");
+
+    let header_predicate = build_row_regex_predicate(vec![
+        "column_name",
+        "data_type",
+        "count",
+        "null_count",
+        "distinct_count",
+        "min",
+        "max",
+        "mean",
+        "stddev",
+        "top_values",
+    ]);
+
+    let data_predicate = build_row_regex_predicate(vec!["url", "Utf8"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_csv_on_stdin() -> datafusion::common::Result<()> {
+    let mut cmd = assert_cmd::Command::from_std(get_qv_cmd()?);
+    let cmd = cmd
+        .arg("-")
+        .arg("-q")
+        .arg("SELECT name FROM tbl WHERE id = 2")
+        .write_stdin("id,name\n1,foo\n2,bar\n");
+
+    let header_predicate = build_row_regex_predicate(vec!["name"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["bar"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_csv_options() -> datafusion::common::Result<()> {
+    let mut cmd = assert_cmd::Command::from_std(get_qv_cmd()?);
+    let cmd = cmd
+        .arg("-")
+        .arg("-q")
+        .arg("SELECT name FROM tbl WHERE amount IS NULL")
+        .arg("--csv-delimiter")
+        .arg(";")
+        .arg("--csv-comment")
+        .arg("#")
+        .arg("--csv-null-value")
+        .arg("NA")
+        .write_stdin("# exported data\nid;name;amount\n1;foo;1.5\n2;bar;NA\n");
+
+    let header_predicate = build_row_regex_predicate(vec!["name"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["bar"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate)
+        .stdout(predicate::str::contains("foo").not());
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_gzip_csv_on_stdin() -> datafusion::common::Result<()> {
+    // gzip compressed "id,name\n1,foo\n2,bar\n"
+    let data: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x4c, 0xd1, 0xc9, 0x4b,
+        0xcc, 0x4d, 0xe5, 0x32, 0xd4, 0x49, 0xcb, 0xcf, 0xe7, 0x32, 0xd2, 0x49, 0x4a, 0x2c, 0xe2,
+        0x02, 0x00, 0xb5, 0x6b, 0x38, 0xab, 0x14, 0x00, 0x00, 0x00,
+    ];
+    let mut cmd = assert_cmd::Command::from_std(get_qv_cmd()?);
+    let cmd = cmd
+        .arg("-")
+        .arg("-q")
+        .arg("SELECT name FROM tbl WHERE id = 2")
+        .write_stdin(data);
+
+    let header_predicate = build_row_regex_predicate(vec!["name"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["bar"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_windows_1252_encoded_csv_file() -> datafusion::common::Result<()> {
+    let data_path = env::temp_dir().join(format!("qv_windows_1252_{}", std::process::id()));
+    std::fs::create_dir_all(&data_path)?;
+    // "caf\u{e9}" in windows-1252
+    std::fs::write(data_path.join("data.csv"), b"id;name\n1;caf\xe9\n2;bar\n")?;
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(data_path.to_str().unwrap())
+        .arg("-q")
+        .arg("SELECT name FROM tbl WHERE id = 1")
+        .arg("--csv-delimiter")
+        .arg(";")
+        .arg("--csv-encoding")
+        .arg("windows-1252");
+
+    let header_predicate = build_row_regex_predicate(vec!["name"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["caf\u{e9}"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+
+    std::fs::remove_dir_all(&data_path).ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_json_array_on_stdin() -> datafusion::common::Result<()> {
+    let mut cmd = assert_cmd::Command::from_std(get_qv_cmd()?);
+    let cmd = cmd
+        .arg("-")
+        .arg("-q")
+        .arg("SELECT name FROM tbl WHERE id = 2")
+        .arg("--json-array")
+        .write_stdin("[{\"id\": 1, \"name\": \"foo\"}, {\"id\": 2, \"name\": \"bar\"}]");
+
+    let header_predicate = build_row_regex_predicate(vec!["name"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["bar"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_detected_json_array_on_stdin() -> datafusion::common::Result<()> {
+    let mut cmd = assert_cmd::Command::from_std(get_qv_cmd()?);
+    let cmd = cmd
+        .arg("-")
+        .arg("-q")
+        .arg("SELECT name FROM tbl WHERE id = 2")
+        .write_stdin("[{\"id\": 1, \"name\": \"foo\"}, {\"id\": 2, \"name\": \"bar\"}]");
+
+    let header_predicate = build_row_regex_predicate(vec!["name"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["bar"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_hive_partitioned_folder() -> datafusion::common::Result<()> {
+    let table_path =
+        env::temp_dir().join(format!("qv_hive_partitioned_folder_{}", std::process::id()));
+    for (dt, region, amount) in [("2024-01-01", "eu", 1), ("2024-01-02", "us", 2)] {
+        let partition_path = table_path.join(format!("dt={dt}/region={region}"));
+        std::fs::create_dir_all(&partition_path)?;
+        std::fs::write(
+            partition_path.join("data.csv"),
+            format!("amount\n{amount}\n"),
+        )?;
+    }
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(table_path.to_str().unwrap())
+        .arg("-q")
+        .arg("SELECT region, amount FROM tbl WHERE dt = '2024-01-02'");
+
+    let header_predicate = build_row_regex_predicate(vec!["region", "amount"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["us", "2"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate)
+        .stdout(predicate::str::contains("eu").not());
+
+    std::fs::remove_dir_all(&table_path).ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_hive_partitioned_folder_with_partition_column_in_files(
+) -> datafusion::common::Result<()> {
+    let table_path = env::temp_dir().join(format!(
+        "qv_hive_partition_column_in_files_{}",
+        std::process::id()
+    ));
+    for (id, amount) in [("001", 1), ("002", 2)] {
+        let partition_path = table_path.join(format!("id={id}"));
+        std::fs::create_dir_all(&partition_path)?;
+        std::fs::write(
+            partition_path.join("data.csv"),
+            format!("id,amount\n{id},{amount}\n"),
+        )?;
+    }
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(table_path.to_str().unwrap())
+        .arg("-q")
+        .arg("SELECT id, amount FROM tbl WHERE amount = 2");
+
+    let header_predicate = build_row_regex_predicate(vec!["id", "amount"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["2", "2"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+
+    std::fs::remove_dir_all(&table_path).ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_hive_partitioned_folder_keeps_leading_zeros() -> datafusion::common::Result<()> {
+    let table_path = env::temp_dir().join(format!(
+        "qv_hive_partition_leading_zeros_{}",
+        std::process::id()
+    ));
+    for (store, amount) in [("001", 1), ("002", 2)] {
+        let partition_path = table_path.join(format!("store={store}"));
+        std::fs::create_dir_all(&partition_path)?;
+        std::fs::write(
+            partition_path.join("data.csv"),
+            format!("amount\n{amount}\n"),
+        )?;
+    }
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(table_path.to_str().unwrap())
+        .arg("-q")
+        .arg("SELECT store FROM tbl WHERE store = '002'");
+
+    let header_predicate = build_row_regex_predicate(vec!["store"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["002"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+
+    std::fs::remove_dir_all(&table_path).ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_hive_partitioned_folder_of_arrow_files() -> datafusion::common::Result<()> {
+    let table_path =
+        env::temp_dir().join(format!("qv_hive_partitioned_arrow_{}", std::process::id()));
+    for (region, amount) in [("eu", 1), ("us", 2)] {
+        let partition_path = table_path.join(format!("region={region}"));
+        std::fs::create_dir_all(&partition_path)?;
+        let mut cmd = get_qv_cmd()?;
+        cmd.arg("-")
+            .arg("-q")
+            .arg("SELECT amount FROM tbl")
+            .arg("--output")
+            .arg(partition_path.join("data.arrow").to_str().unwrap());
+        assert_cmd::Command::from_std(cmd)
+            .write_stdin(format!("amount\n{amount}\n"))
+            .assert()
+            .success();
+    }
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(table_path.to_str().unwrap())
+        .arg("-q")
+        .arg("SELECT region, amount FROM tbl WHERE region = 'us'");
+
+    let header_predicate = build_row_regex_predicate(vec!["region", "amount"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["us", "2"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate)
+        .stdout(predicate::str::contains("eu").not());
+
+    std::fs::remove_dir_all(&table_path).ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_local_orc_file_with_filter() -> datafusion::common::Result<()> {
+    let data_path = env::temp_dir().join(format!("qv_orc_{}", std::process::id()));
+    std::fs::create_dir_all(&data_path)?;
+    let file_path = data_path.join("data.orc");
+
+    // small stripes, so the filter only matches some of the stripes
+    let ctx = datafusion::prelude::SessionContext::new();
+    let mut writer = None;
+    for (id, name) in [(1, "foo"), (2, "bar"), (3, "baz"), (4, "qux")] {
+        let batches = ctx
+            .sql(&format!(
+                "SELECT CAST({id} AS BIGINT) AS id, '{name}' AS name"
+            ))
+            .await?
+            .collect()
+            .await?;
+        let writer = match &mut writer {
+            Some(writer) => writer,
+            None => writer.insert(
+                orc_rust::ArrowWriterBuilder::new(
+                    std::fs::File::create(&file_path)?,
+                    batches[0].schema(),
+                )
+                .with_stripe_byte_size(1)
+                .try_build()
+                .unwrap(),
+            ),
+        };
+        for batch in &batches {
+            writer.write(batch).unwrap();
+        }
+    }
+    writer.unwrap().close().unwrap();
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(file_path.to_str().unwrap())
+        .arg("-q")
+        .arg("SELECT id, name FROM tbl WHERE id >= 3 ORDER BY id");
+
+    let header_predicate = build_row_regex_predicate(vec!["id", "name"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["3", "baz"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate)
+        .stdout(predicate::str::contains("qux"))
+        .stdout(predicate::str::contains("foo").not())
+        .stdout(predicate::str::contains("bar").not());
+
+    std::fs::remove_dir_all(&data_path).ok();
+    Ok(())
+}
+
+fn write_avro_file(path: &std::path::Path, schema: &str, records: Vec<apache_avro::types::Value>) {
+    let schema = apache_avro::Schema::parse_str(schema).unwrap();
+    let mut writer = apache_avro::Writer::new(&schema, vec![]);
+    for record in records {
+        writer.append(record).unwrap();
+    }
+    std::fs::write(path, writer.into_inner().unwrap()).unwrap();
+}
+
+/// Writes an iceberg table with a single data file, the current schema has a column (comment)
+/// which was added after the data file was written
+async fn write_iceberg_table(table_path: &std::path::Path) -> datafusion::common::Result<()> {
+    use apache_avro::types::Value;
+    use datafusion::dataframe::DataFrameWriteOptions;
+
+    let data_path = table_path.join("data/00000-0.parquet");
+    let metadata_path = table_path.join("metadata");
+    std::fs::create_dir_all(&metadata_path)?;
+    datafusion::prelude::SessionContext::new()
+        .sql("SELECT CAST(1 AS BIGINT) AS id, 'foo' AS name")
+        .await?
+        .write_parquet(
+            data_path.to_str().unwrap(),
+            DataFrameWriteOptions::new(),
+            None,
+        )
+        .await?;
+
+    let manifest_path = metadata_path.join("manifest-0.avro");
+    write_avro_file(
+        &manifest_path,
+        r#"{"type": "record", "name": "manifest_entry", "fields": [
+            {"name": "status", "type": "int"},
+            {"name": "data_file", "type": {"type": "record", "name": "r2", "fields": [
+                {"name": "content", "type": "int"},
+                {"name": "file_path", "type": "string"},
+                {"name": "file_format", "type": "string"}
+            ]}}
+        ]}"#,
+        vec![Value::Record(vec![
+            ("status".to_string(), Value::Int(1)),
+            (
+                "data_file".to_string(),
+                Value::Record(vec![
+                    ("content".to_string(), Value::Int(0)),
+                    (
+                        "file_path".to_string(),
+                        Value::String(data_path.to_str().unwrap().to_string()),
+                    ),
+                    (
+                        "file_format".to_string(),
+                        Value::String("PARQUET".to_string()),
+                    ),
+                ]),
+            ),
+        ])],
+    );
+
+    let manifest_list_path = metadata_path.join("snap-1.avro");
+    write_avro_file(
+        &manifest_list_path,
+        r#"{"type": "record", "name": "manifest_file", "fields": [
+            {"name": "manifest_path", "type": "string"},
+            {"name": "content", "type": "int"}
+        ]}"#,
+        vec![Value::Record(vec![
+            (
+                "manifest_path".to_string(),
+                Value::String(manifest_path.to_str().unwrap().to_string()),
+            ),
+            ("content".to_string(), Value::Int(0)),
+        ])],
+    );
+
+    let metadata = format!(
+        r#"{{
+            "location": "{}",
+            "current-snapshot-id": 1,
+            "current-schema-id": 1,
+            "schemas": [
+                {{"schema-id": 0, "fields": [
+                    {{"id": 1, "name": "id", "required": false, "type": "long"}},
+                    {{"id": 2, "name": "name", "required": false, "type": "string"}}
+                ]}},
+                {{"schema-id": 1, "fields": [
+                    {{"id": 1, "name": "id", "required": false, "type": "long"}},
+                    {{"id": 2, "name": "name", "required": false, "type": "string"}},
+                    {{"id": 3, "name": "comment", "required": false, "type": "string"}}
+                ]}}
+            ],
+            "snapshots": [
+                {{"snapshot-id": 1, "timestamp-ms": 1700000000000, "schema-id": 0, "manifest-list": "{}"}}
+            ]
+        }}"#,
+        table_path.to_str().unwrap(),
+        manifest_list_path.to_str().unwrap()
+    );
+    std::fs::write(metadata_path.join("v1.metadata.json"), metadata)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_local_iceberg_table_with_added_column() -> datafusion::common::Result<()> {
+    let table_path = env::temp_dir().join(format!("qv_iceberg_table_{}", std::process::id()));
+    write_iceberg_table(&table_path).await?;
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(table_path.to_str().unwrap())
+        .arg("-q")
+        .arg("SELECT id, name, comment IS NULL AS no_comment FROM tbl");
+
+    let header_predicate = build_row_regex_predicate(vec!["id", "name", "no_comment"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["1", "foo", "true"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate);
+
+    std::fs::remove_dir_all(&table_path).ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_empty_local_iceberg_table() -> datafusion::common::Result<()> {
+    let table_path = env::temp_dir().join(format!("qv_empty_iceberg_table_{}", std::process::id()));
+    let metadata_path = table_path.join("metadata");
+    std::fs::create_dir_all(&metadata_path)?;
+    std::fs::write(
+        metadata_path.join("v1.metadata.json"),
+        r#"{
+            "location": "/tmp/empty",
+            "current-snapshot-id": -1,
+            "current-schema-id": 0,
+            "schemas": [{"schema-id": 0, "fields": [{"id": 1, "name": "id", "required": true, "type": "long"}]}],
+            "snapshots": []
+        }"#,
+    )?;
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(table_path.to_str().unwrap())
+        .arg("-q")
+        .arg("SELECT count(*) AS row_count FROM tbl");
+
+    let data_predicate = build_row_regex_predicate(vec!["row_count"]);
+
+    cmd.assert()
+        .success()
+        .stdout(data_predicate)
+        .stdout(predicate::str::is_match(r"\|\s*0\s*\|").unwrap());
+
+    std::fs::remove_dir_all(&table_path).ok();
+    Ok(())
+}
+
+/// Writes a delta table with the change data feed enabled and a data file added by every commit
+async fn write_delta_table_with_cdf(
+    table_path: &std::path::Path,
+) -> datafusion::common::Result<()> {
+    use datafusion::dataframe::DataFrameWriteOptions;
+
+    let log_path = table_path.join("_delta_log");
+    std::fs::create_dir_all(&log_path)?;
+    let ctx = datafusion::prelude::SessionContext::new();
+    for version in 0..2 {
+        let file_name = format!("part-0000{version}.parquet");
+        let file_path = table_path.join(&file_name);
+        ctx.sql(&format!("SELECT CAST({} AS BIGINT) AS id", version + 1))
+            .await?
+            .write_parquet(
+                file_path.to_str().unwrap(),
+                DataFrameWriteOptions::new(),
+                None,
+            )
+            .await?;
+        let size = std::fs::metadata(&file_path)?.len();
+        let timestamp = 1700000000000i64 + version * 1000;
+
+        let mut actions = vec![];
+        if version == 0 {
+            actions.push(r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":4}}"#.to_string());
+            actions.push(format!(
+                r#"{{"metaData":{{"id":"qv-cdf-test","format":{{"provider":"parquet","options":{{}}}},"schemaString":"{{\"type\":\"struct\",\"fields\":[{{\"name\":\"id\",\"type\":\"long\",\"nullable\":true,\"metadata\":{{}}}}]}}","partitionColumns":[],"configuration":{{"delta.enableChangeDataFeed":"true"}},"createdTime":{timestamp}}}}}"#
+            ));
+        }
+        actions.push(format!(
+            r#"{{"add":{{"path":"{file_name}","partitionValues":{{}},"size":{size},"modificationTime":{timestamp},"dataChange":true}}}}"#
+        ));
+        actions.push(format!(
+            r#"{{"commitInfo":{{"timestamp":{timestamp},"operation":"WRITE"}}}}"#
+        ));
+        std::fs::write(
+            log_path.join(format!("{version:020}.json")),
+            actions.join("\n") + "\n",
+        )?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_local_deltalake_change_data_feed() -> datafusion::common::Result<()> {
+    let table_path = env::temp_dir().join(format!("qv_delta_cdf_{}", std::process::id()));
+    write_delta_table_with_cdf(&table_path).await?;
+
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(table_path.to_str().unwrap())
+        .arg("--cdf-from")
+        .arg("1")
+        .arg("--cdf-to")
+        .arg("1")
+        .arg("-q")
+        .arg("SELECT id, _change_type, _commit_version FROM tbl");
+
+    let header_predicate = build_row_regex_predicate(vec!["id", "_change_type", "_commit_version"]);
+
+    let data_predicate = build_row_regex_predicate(vec!["2", "insert", "1"]);
+
+    cmd.assert()
+        .success()
+        .stdout(header_predicate)
+        .stdout(data_predicate)
+        .stdout(
+            predicate::str::is_match(r"\|\s*1\s*\|\s*insert")
+                .unwrap()
+                .not(),
+        );
+
+    std::fs::remove_dir_all(&table_path).ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_change_data_feed_of_files_fails() -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path("data/json/ndjson-sample.json"))
+        .arg("--cdf-from")
+        .arg("0");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a delta table"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_table_version_of_files_fails() -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path("data/json/ndjson-sample.json"))
+        .arg("--table-version")
+        .arg("0");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a delta table"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_snapshot_id_of_files_fails() -> datafusion::common::Result<()> {
+    let mut cmd = get_qv_cmd()?;
+    let cmd = cmd
+        .arg(get_qv_testing_path("data/json/ndjson-sample.json"))
+        .arg("--snapshot-id")
+        .arg("1");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is not an iceberg table"));
+    Ok(())
+}